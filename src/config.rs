@@ -0,0 +1,206 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use crate::hooks::HooksConfig;
+
+/// The user configuration, read from a TOML file. Every section is optional
+/// and falls back to its defaults when missing.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub picker: PickerConfig,
+    pub templates: BTreeMap<String, TemplateConfig>,
+    pub tmux: TmuxConfig,
+    pub hooks: HooksConfig,
+}
+
+/// The external program used by `muxwm pick`. It follows the dmenu protocol:
+/// candidates are written to its stdin, one per line, and the selected line
+/// is read back from its stdout.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct PickerConfig {
+    /// the program and its arguments, for example `["fzf"]`
+    pub command: Vec<String>,
+}
+
+impl Default for PickerConfig {
+    fn default() -> Self {
+        Self {
+            command: ["rofi", "-dmenu", "-i", "-p", "muxwm"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+        }
+    }
+}
+
+/// A project template: the views created along with a new project.
+#[derive(Debug, Deserialize)]
+pub struct TemplateConfig {
+    /// the names of the views, in order
+    pub views: Vec<String>,
+    /// the view that starts out as the project's active view, the first view
+    /// when not set
+    #[serde(default)]
+    pub active: Option<String>,
+}
+
+/// The pairing of projects with tmux sessions.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct TmuxConfig {
+    /// create the project's tmux session when the project is focused
+    pub enabled: bool,
+    /// the tmux server socket name (`tmux -L`), the default server when not set
+    pub socket_name: Option<String>,
+}
+
+impl Config {
+    /// Get the template with the given name.
+    pub fn template(&self, name: &str) -> Result<&TemplateConfig> {
+        self.templates.get(name).ok_or_else(|| {
+            anyhow::anyhow!(
+                "no template named '{}', known templates: {}",
+                name,
+                self.templates
+                    .keys()
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        })
+    }
+
+    /// Load the configuration from `path`, or from the default location when
+    /// no path is given. A missing file at the default location is not an
+    /// error, the defaults are used instead.
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        match path {
+            Some(path) => Self::read(path),
+            None => match default_path() {
+                Some(path) if path.exists() => Self::read(&path),
+                _ => Ok(Self::default()),
+            },
+        }
+    }
+
+    fn read(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("reading config file {}", path.display()))?;
+        Self::parse(&contents).with_context(|| format!("parsing config file {}", path.display()))
+    }
+
+    fn parse(contents: &str) -> Result<Self> {
+        Ok(toml::from_str(contents)?)
+    }
+}
+
+/// the directory holding muxwm's config file and manifest
+pub fn config_dir() -> Option<PathBuf> {
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_dir.join("muxwm"))
+}
+
+fn default_path() -> Option<PathBuf> {
+    Some(config_dir()?.join("config.toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Config;
+
+    #[test]
+    fn test_parse_empty_config_uses_defaults() {
+        let config = Config::parse("").unwrap();
+        assert_eq!(config.picker.command[0], "rofi");
+    }
+
+    #[test]
+    fn test_parse_picker_command() {
+        let config = Config::parse(
+            r#"
+            [picker]
+            command = ["fzf", "--reverse"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.picker.command, vec!["fzf", "--reverse"]);
+    }
+
+    #[test]
+    fn test_parse_templates() {
+        let config = Config::parse(
+            r#"
+            [templates.dev]
+            views = ["code", "term", "docs"]
+            active = "term"
+
+            [templates.plain]
+            views = ["main"]
+            "#,
+        )
+        .unwrap();
+
+        let dev = config.template("dev").unwrap();
+        assert_eq!(dev.views, vec!["code", "term", "docs"]);
+        assert_eq!(dev.active.as_deref(), Some("term"));
+
+        let plain = config.template("plain").unwrap();
+        assert!(plain.active.is_none());
+    }
+
+    #[test]
+    fn test_template_when_not_found_lists_known_templates() {
+        let config = Config::parse(
+            r#"
+            [templates.dev]
+            views = ["code"]
+            "#,
+        )
+        .unwrap();
+
+        let err = config.template("nope").unwrap_err();
+        assert!(err.to_string().contains("dev"));
+    }
+
+    #[test]
+    fn test_parse_tmux() {
+        let config = Config::parse("").unwrap();
+        assert!(!config.tmux.enabled);
+
+        let config = Config::parse(
+            r#"
+            [tmux]
+            enabled = true
+            socket_name = "work"
+            "#,
+        )
+        .unwrap();
+        assert!(config.tmux.enabled);
+        assert_eq!(config.tmux.socket_name.as_deref(), Some("work"));
+    }
+
+    #[test]
+    fn test_parse_hooks() {
+        let config = Config::parse(
+            r#"
+            [hooks]
+            project_focused = ["notify-send focused", "~/bin/track"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.hooks.project_focused.len(), 2);
+        assert!(config.hooks.pin_set.is_empty());
+    }
+
+    #[test]
+    fn test_parse_invalid_config() {
+        assert!(Config::parse("picker = 1").is_err());
+    }
+}
@@ -0,0 +1,214 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+/// The user scripts run on each event, as shell command lines.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct HooksConfig {
+    pub project_created: Vec<String>,
+    pub project_focused: Vec<String>,
+    pub view_focused: Vec<String>,
+    pub view_renamed: Vec<String>,
+    pub pin_set: Vec<String>,
+}
+
+/// Something that happened to the muxwm state, passed on to the hooks.
+pub enum Event<'a> {
+    ProjectCreated {
+        project: &'a str,
+    },
+    ProjectFocused {
+        project: &'a str,
+        workspace: &'a str,
+    },
+    ViewFocused {
+        project: &'a str,
+        view: &'a str,
+        workspace: &'a str,
+    },
+    ViewRenamed {
+        project: &'a str,
+        old_view: &'a str,
+        view: &'a str,
+        workspace: &'a str,
+    },
+    PinSet {
+        key: &'a str,
+        pin_type: &'a str,
+        workspace: &'a str,
+    },
+}
+
+impl Event<'_> {
+    fn name(&self) -> &'static str {
+        match self {
+            Event::ProjectCreated { .. } => "project_created",
+            Event::ProjectFocused { .. } => "project_focused",
+            Event::ViewFocused { .. } => "view_focused",
+            Event::ViewRenamed { .. } => "view_renamed",
+            Event::PinSet { .. } => "pin_set",
+        }
+    }
+
+    fn fields(&self) -> Vec<(&'static str, &str)> {
+        match self {
+            Event::ProjectCreated { project } => vec![("project", project)],
+            Event::ProjectFocused { project, workspace } => {
+                vec![("project", project), ("workspace", workspace)]
+            }
+            Event::ViewFocused {
+                project,
+                view,
+                workspace,
+            } => vec![
+                ("project", project),
+                ("view", view),
+                ("workspace", workspace),
+            ],
+            Event::ViewRenamed {
+                project,
+                old_view,
+                view,
+                workspace,
+            } => vec![
+                ("project", project),
+                ("old_view", old_view),
+                ("view", view),
+                ("workspace", workspace),
+            ],
+            Event::PinSet {
+                key,
+                pin_type,
+                workspace,
+            } => vec![
+                ("key", key),
+                ("pin_type", pin_type),
+                ("workspace", workspace),
+            ],
+        }
+    }
+
+    /// the event as a JSON object, `{"event": <name>, <field>: <value>, ...}`
+    fn to_json(&self) -> String {
+        let mut object = Map::new();
+        object.insert("event".to_string(), Value::from(self.name()));
+        for (field, value) in self.fields() {
+            object.insert(field.to_string(), Value::from(value));
+        }
+        Value::Object(object).to_string()
+    }
+}
+
+impl HooksConfig {
+    fn commands(&self, event: &Event) -> &[String] {
+        match event {
+            Event::ProjectCreated { .. } => &self.project_created,
+            Event::ProjectFocused { .. } => &self.project_focused,
+            Event::ViewFocused { .. } => &self.view_focused,
+            Event::ViewRenamed { .. } => &self.view_renamed,
+            Event::PinSet { .. } => &self.pin_set,
+        }
+    }
+
+    /// Run the hooks configured for the event, one after the other. Each hook
+    /// gets the event as `MUXWM_*` environment variables and as JSON on its
+    /// stdin. The change that triggered the event is already committed, so a
+    /// failing hook is reported on stderr instead of failing the command.
+    pub fn run(&self, event: &Event) {
+        for command in self.commands(event) {
+            if let Err(err) = run_hook(command, event) {
+                eprintln!(
+                    "muxwm: {} hook '{}' failed: {:#}",
+                    event.name(),
+                    command,
+                    err
+                );
+            }
+        }
+    }
+}
+
+fn run_hook(command: &str, event: &Event) -> Result<()> {
+    let mut cmd = Command::new("sh");
+    cmd.args(["-c", command])
+        .env("MUXWM_EVENT", event.name())
+        .stdin(Stdio::piped());
+    for (field, value) in event.fields() {
+        cmd.env(format!("MUXWM_{}", field.to_uppercase()), value);
+    }
+
+    let mut child = cmd.spawn().context("starting hook")?;
+    // the hook may not read its stdin, which is fine
+    let _ = child
+        .stdin
+        .take()
+        .expect("INTERNAL ERROR: hook stdin is piped")
+        .write_all(event.to_json().as_bytes());
+
+    let status = child.wait().context("waiting for hook")?;
+    if !status.success() {
+        return Err(anyhow::anyhow!("exited with {}", status));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Event, HooksConfig, run_hook};
+
+    fn output_file(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("muxwm-hook-{}-{}", name, std::process::id()))
+    }
+
+    #[test]
+    fn test_run_hook_passes_event_in_env_and_stdin() {
+        let out = output_file("env");
+        let event = Event::ViewRenamed {
+            project: "proj1",
+            old_view: "view0",
+            view: "code",
+            workspace: "proj1#code",
+        };
+
+        let command = format!(
+            "echo \"$MUXWM_EVENT $MUXWM_PROJECT $MUXWM_OLD_VIEW $MUXWM_VIEW\" > {0}; cat >> {0}",
+            out.display()
+        );
+        run_hook(&command, &event).unwrap();
+
+        let written = std::fs::read_to_string(&out).unwrap();
+        std::fs::remove_file(&out).unwrap();
+        let (env, json) = written.split_once('\n').unwrap();
+        assert_eq!(env, "view_renamed proj1 view0 code");
+
+        let json: serde_json::Value = serde_json::from_str(json).unwrap();
+        assert_eq!(json["event"], "view_renamed");
+        assert_eq!(json["workspace"], "proj1#code");
+    }
+
+    #[test]
+    fn test_run_hook_when_hook_fails() {
+        let event = Event::ProjectCreated { project: "proj1" };
+        assert!(run_hook("exit 3", &event).is_err());
+    }
+
+    #[test]
+    fn test_run_only_runs_the_hooks_of_the_event() {
+        let out = output_file("select");
+        let hooks = HooksConfig {
+            project_created: vec![format!("echo created >> {}", out.display())],
+            pin_set: vec![format!("echo pin >> {}", out.display())],
+            ..Default::default()
+        };
+
+        hooks.run(&Event::ProjectCreated { project: "proj1" });
+
+        let written = std::fs::read_to_string(&out).unwrap();
+        std::fs::remove_file(&out).unwrap();
+        assert_eq!(written, "created\n");
+    }
+}
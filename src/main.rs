@@ -1,13 +1,29 @@
 use std::collections::HashSet;
-use std::path::PathBuf;
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
-use clap::{Parser, Subcommand};
-use i3ipc::I3Connection;
+use clap::{Parser, Subcommand, ValueEnum};
 
+mod config;
+mod hooks;
+mod manifest;
 mod model;
-use model::Repository;
+mod picker;
+mod report;
+mod state;
+mod tmux;
+mod wm;
+use config::Config;
+use hooks::Event;
+use model::{
+    ImportMode, NameMatch, PinTarget, PinType, Project, ReportGrouping, Repository, SortOrder,
+    View, is_window_manager_display_name, parse_view_path, sanitize_project_name,
+};
+use picker::Entry;
 use rusqlite::Connection;
+use tmux::Tmux;
+use wm::{Destination, Window, WindowManager};
 
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
@@ -23,6 +39,11 @@ struct Cli {
     #[arg(short, long, action = clap::ArgAction::Count)]
     debug: u8,
 
+    /// Only accept exact project and view names, instead of unambiguous
+    /// prefixes and fuzzy matches
+    #[arg(long, global = true)]
+    exact: bool,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -46,30 +67,310 @@ enum Commands {
         #[command(subcommand)]
         command: ViewCommands,
     },
+
+    /// perform operations on the windows on views
+    Window {
+        #[command(subcommand)]
+        command: WindowCommands,
+    },
+
+    /// choose an object with the configured picker (rofi, dmenu, fzf, ...)
+    /// and focus it
+    Pick {
+        /// the kind of object to choose from
+        #[arg(value_enum)]
+        target: PickTarget,
+    },
+
+    /// write all projects, views and pins to a versioned JSON or TOML
+    /// document
+    Export {
+        /// the file to write, standard output when not given
+        file: Option<PathBuf>,
+
+        /// the document format (default: from the file extension, or JSON)
+        #[arg(long, value_enum)]
+        format: Option<StateFormat>,
+    },
+
+    /// load projects, views and pins from a document written by `muxwm
+    /// export`
+    Import {
+        /// the file to read
+        file: PathBuf,
+
+        /// the document format (default: from the file extension, or JSON)
+        #[arg(long, value_enum)]
+        format: Option<StateFormat>,
+
+        /// whether to keep the existing projects and pins
+        #[arg(long, value_enum, default_value_t = ImportStrategy::Merge)]
+        mode: ImportStrategy,
+
+        /// only print what would change and any conflicts
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// change projects, views and pins to match the manifest, after showing
+    /// the planned changes
+    Sync {
+        /// the manifest file (default: manifest.toml next to the config file)
+        #[arg(long, value_name = "FILE")]
+        manifest: Option<PathBuf>,
+
+        /// apply the plan without asking for confirmation
+        #[arg(long, short)]
+        yes: bool,
+
+        /// only print the plan
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// revert the most recent changes to projects, views and pins
+    Undo {
+        /// the number of operations to revert
+        #[arg(default_value_t = 1)]
+        count: usize,
+    },
+
+    /// list the most recent changes to projects, views and pins, newest
+    /// first
+    History {
+        /// the number of operations to list
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
+    },
+
+    /// total the time spent focused on each project or view
+    ///
+    /// Only focus changes made through muxwm (focusing a project, view or pin,
+    /// picking, ...) are recorded. Switching workspaces with i3 directly is not
+    /// seen, so that time is counted towards the view focused before.
+    Report {
+        /// only count time from this local date on (YYYY-MM-DD, optionally
+        /// followed by HH:MM). Counts all recorded time when not given.
+        #[arg(long)]
+        since: Option<String>,
+
+        /// total the time per project or per view
+        #[arg(long, value_enum, default_value_t = ReportBy::Project)]
+        by: ReportBy,
+
+        /// the output format
+        #[arg(long, value_enum, default_value_t = ReportFormat::Table)]
+        format: ReportFormat,
+    },
+}
+
+/// the file format of `muxwm export` and `muxwm import`
+#[derive(Clone, Copy, ValueEnum)]
+enum StateFormat {
+    Json,
+    Toml,
+}
+
+impl From<StateFormat> for state::Format {
+    fn from(format: StateFormat) -> Self {
+        match format {
+            StateFormat::Json => state::Format::Json,
+            StateFormat::Toml => state::Format::Toml,
+        }
+    }
+}
+
+/// how `muxwm import` treats the existing projects and pins
+#[derive(Clone, Copy, ValueEnum)]
+enum ImportStrategy {
+    /// add the imported projects and pins, refusing duplicate names and keys
+    Merge,
+    /// remove all existing projects and pins first
+    Replace,
+}
+
+impl From<ImportStrategy> for ImportMode {
+    fn from(strategy: ImportStrategy) -> Self {
+        match strategy {
+            ImportStrategy::Merge => ImportMode::Merge,
+            ImportStrategy::Replace => ImportMode::Replace,
+        }
+    }
+}
+
+/// how `muxwm report` totals focus time
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum ReportBy {
+    /// one line per project
+    Project,
+    /// one line per view
+    View,
+}
+
+impl From<ReportBy> for ReportGrouping {
+    fn from(by: ReportBy) -> Self {
+        match by {
+            ReportBy::Project => ReportGrouping::Project,
+            ReportBy::View => ReportGrouping::View,
+        }
+    }
+}
+
+/// the output format of `muxwm report`
+#[derive(Clone, Copy, ValueEnum)]
+enum ReportFormat {
+    /// tab separated, with H:MM:SS durations
+    Table,
+    /// comma separated, with durations in seconds
+    Csv,
+    /// an array of objects, with durations in seconds
+    Json,
+}
+
+/// the kind of object offered by `muxwm pick`
+#[derive(Clone, Copy, ValueEnum)]
+enum PickTarget {
+    /// pick a project and focus its active view
+    Project,
+    /// pick any view of any project
+    View,
+    /// pick a pin and focus what it points to
+    Pin,
+}
+
+/// the order in which list commands print their entries
+#[derive(Clone, Copy, ValueEnum)]
+enum ListSort {
+    /// most recently focused first
+    Mru,
+    /// alphabetically by name
+    Name,
+    /// in creation order
+    Created,
+    /// by position within the project
+    Position,
+}
+
+impl From<ListSort> for SortOrder {
+    fn from(sort: ListSort) -> Self {
+        match sort {
+            ListSort::Mru => SortOrder::MostRecentlyUsed,
+            ListSort::Name => SortOrder::Name,
+            ListSort::Created => SortOrder::Created,
+            ListSort::Position => SortOrder::Position,
+        }
+    }
 }
 
 #[derive(Subcommand)]
 enum PinCommands {
-    /// set the focus on the current screen to the specified view
+    /// set the focus on the current screen to the specified view. Focusing
+    /// a stack again moves on to its next view.
     Focus {
         /// the pin key of the view on which to focus
         key: String,
     },
 
-    /// set the pin to the currently focused view
+    /// set the pin to the currently focused view, or to the given project or
+    /// view
     Set {
         /// the pin key of the view on which to focus
         key: String,
 
-        /// if true, the pin will be a project pin
-        #[arg(long)]
-        project: bool,
+        /// pin a project instead of a view: the named project, or the
+        /// current project when no name is given
+        #[arg(long, value_name = "NAME", num_args = 0..=1)]
+        project: Option<Option<String>>,
+
+        /// pin the named view instead of the current view
+        #[arg(long, value_name = "[PROJECT/]VIEW", conflicts_with = "project")]
+        view: Option<String>,
+
+        /// if true, the pin key only applies within the view's project, and
+        /// takes precedence over a global pin with the same key there
+        #[arg(long, conflicts_with = "project")]
+        local: bool,
+
+        /// pin a workspace that is not a muxwm view: the named workspace, or
+        /// the focused workspace when no name is given
+        #[arg(
+            long,
+            value_name = "NAME",
+            num_args = 0..=1,
+            conflicts_with_all = ["project", "view", "local"]
+        )]
+        workspace: Option<Option<String>>,
+
+        /// pin a window by its i3 con_id: the given window, or the focused
+        /// window when no con_id is given
+        #[arg(
+            long,
+            value_name = "CON_ID",
+            num_args = 0..=1,
+            conflicts_with_all = ["project", "view", "local", "workspace"]
+        )]
+        window: Option<Option<i64>>,
+
+        /// pin the window with the i3 mark
+        #[arg(
+            long,
+            value_name = "MARK",
+            conflicts_with_all = ["project", "view", "local", "workspace", "window"]
+        )]
+        mark: Option<String>,
+
+        /// repoint the key when it already points to something else
+        #[arg(long, short)]
+        force: bool,
     },
 
     /// clear the pin
     Clear {
         /// the pin key of the view to clear
         key: String,
+
+        /// if true, clear the current project's local pin instead of the
+        /// global pin
+        #[arg(long)]
+        local: bool,
+    },
+
+    /// add the currently focused view, or the given view, to the end of the
+    /// pin stack under the key, creating the stack when the key is unused
+    Add {
+        /// the key of the stack
+        key: String,
+
+        /// add the named view instead of the current view
+        #[arg(long, value_name = "[PROJECT/]VIEW")]
+        view: Option<String>,
+    },
+
+    /// take the currently focused view, or the given view, out of the pin
+    /// stack under the key. The stack is removed with its last view.
+    Remove {
+        /// the key of the stack
+        key: String,
+
+        /// remove the named view instead of the current view
+        #[arg(long, value_name = "[PROJECT/]VIEW")]
+        view: Option<String>,
+    },
+
+    /// exchange what two pins point to, without focusing anything
+    Swap {
+        /// the first pin key
+        a: String,
+        /// the second pin key
+        b: String,
+    },
+
+    /// give a pin a new, unused key, without focusing anything
+    Rename {
+        /// the current pin key
+        old: String,
+        /// the new pin key
+        new: String,
     },
 
     /// list all pins
@@ -82,6 +383,28 @@ enum ProjectCommands {
     Add {
         /// the name of the project
         name: String,
+
+        /// focus the new project's active view once it is created
+        /// (default: false)
+        #[arg(long)]
+        focus: bool,
+
+        /// create the views of the named template from the config file
+        /// instead of a single default view
+        #[arg(long)]
+        template: Option<String>,
+    },
+
+    /// focus on the project's active view, creating the project first if
+    /// no project matches the name (use --exact to create a project whose
+    /// name is a prefix of an existing one)
+    Open {
+        /// the name of the project
+        name: String,
+
+        /// the template to create the project from, if it does not exist
+        #[arg(long)]
+        template: Option<String>,
     },
 
     /// list all projects
@@ -90,6 +413,10 @@ enum ProjectCommands {
         /// (default: false)
         #[arg(long)]
         with_pins: bool,
+
+        /// the order in which to list the projects
+        #[arg(long, value_enum, default_value_t = ListSort::Created)]
+        sort: ListSort,
     },
 
     /// focus on the project's active view
@@ -98,6 +425,44 @@ enum ProjectCommands {
         name: String,
     },
 
+    /// remove a project along with its views and pins
+    Remove {
+        /// the name of the project, a name that is not exact has to be
+        /// confirmed
+        name: String,
+
+        /// also kill the project's tmux session
+        /// (default: false)
+        #[arg(long)]
+        kill_tmux_session: bool,
+    },
+
+    /// kill every window on the views of a project, after listing them and
+    /// asking for confirmation. The project itself is kept, so that it can be
+    /// opened again.
+    Close {
+        /// the name of the project, a name that is not exact has to be
+        /// confirmed
+        name: String,
+
+        /// kill the windows without asking for confirmation, a name that is
+        /// not exact still has to be confirmed
+        #[arg(long, short)]
+        yes: bool,
+
+        /// only list the windows
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// set the name of the tmux session paired with the current active
+    /// project
+    SetTmuxSession {
+        /// the session name, go back to the default of naming the session
+        /// after the project when omitted
+        session: Option<String>,
+    },
+
     /// update the current active project's active view to the next view
     /// in the view list and focus it
     ActivateNextView {},
@@ -106,14 +471,165 @@ enum ProjectCommands {
     /// in the view list and focus it
     ActivatePrevView {},
 
+    /// focus a view of the current active project, by position or by name,
+    /// and make it the project's active view
+    FocusView {
+        /// the position of the view in the project's view list, starting at 1
+        #[arg(
+            required_unless_present = "name",
+            conflicts_with_all = ["name", "create"],
+            value_parser = clap::value_parser!(i64).range(1..)
+        )]
+        index: Option<i64>,
+
+        /// the name of the view
+        #[arg(long)]
+        name: Option<String>,
+
+        /// create the view if the project has no view with exactly that name
+        /// (default: false)
+        #[arg(long, requires = "name")]
+        create: bool,
+    },
+
     /// add a new view to the current active project
     AddView {
         /// the name of the view
         view_name: String,
+
+        /// make the new view the project's active view and focus it
+        /// (default: false)
+        #[arg(long)]
+        focus: bool,
     },
 
     /// list all views for the current active project
-    ListViews {},
+    ListViews {
+        /// the order in which to list the views
+        #[arg(long, value_enum, default_value_t = ListSort::Position)]
+        sort: ListSort,
+    },
+
+    /// add a project for a directory, named after the directory and with the
+    /// directory as its root. Inside a git repository, the repository's top
+    /// level directory is used.
+    FromDir {
+        /// the directory of the project
+        #[arg(required_unless_present = "scan", conflicts_with = "scan")]
+        path: Option<PathBuf>,
+
+        /// the name of the project (default: derived from the directory name)
+        #[arg(long)]
+        name: Option<String>,
+
+        /// create the views of the named template from the config file
+        #[arg(long)]
+        template: Option<String>,
+
+        /// focus the new project's active view once it is created
+        /// (default: false)
+        #[arg(long)]
+        focus: bool,
+
+        /// instead of adding a project, list the git repositories directly
+        /// inside DIR that are not registered as a project yet
+        #[arg(long, value_name = "DIR")]
+        scan: Option<PathBuf>,
+    },
+
+    /// set the root directory of the current active project
+    SetRoot {
+        /// the directory, unset the root when omitted
+        dir: Option<PathBuf>,
+    },
+
+    /// set an environment variable of the current active project
+    SetEnv {
+        /// the variable, as NAME=VALUE
+        #[arg(value_parser = parse_env_assignment)]
+        assignment: (String, String),
+    },
+
+    /// unset an environment variable of the current active project
+    UnsetEnv {
+        /// the name of the variable
+        name: String,
+    },
+
+    /// list the environment variables of the current active project
+    Env {},
+
+    /// print the root directory of a project
+    Cwd {
+        /// the name of the project (default: the current active project)
+        #[arg(long)]
+        project: Option<String>,
+    },
+
+    /// list the windows on the views of a project, with their con_id, view,
+    /// class and title
+    Windows {
+        /// the name of the project (default: the current active project)
+        #[arg(long)]
+        project: Option<String>,
+    },
+
+    /// run a command in the root directory of a project, with the project's
+    /// environment variables set
+    Exec {
+        /// the name of the project (default: the current active project)
+        #[arg(long)]
+        project: Option<String>,
+
+        /// the command and its arguments
+        #[arg(last = true, required = true)]
+        command: Vec<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum WindowCommands {
+    /// show the project and view the focused window, or the given window,
+    /// is on
+    Which {
+        /// the i3 con_id of the window (default: the focused window)
+        con_id: Option<i64>,
+    },
+
+    /// move the focused window to a view, to a project's active view, or to
+    /// what a pin points to
+    Send {
+        /// the view to move the window to
+        #[arg(
+            long,
+            value_name = "[PROJECT/]VIEW",
+            required_unless_present_any = ["project", "pin"],
+            conflicts_with_all = ["project", "pin"]
+        )]
+        view: Option<String>,
+
+        /// the project to move the window to, onto its active view
+        #[arg(long, value_name = "NAME", conflicts_with = "pin")]
+        project: Option<String>,
+
+        /// the pin key to move the window to. Windows sent to a window pin
+        /// end up on that window's workspace, next to it for a mark.
+        #[arg(long, value_name = "KEY")]
+        pin: Option<String>,
+
+        /// focus the window again where it was moved to
+        /// (default: false)
+        #[arg(long)]
+        follow: bool,
+    },
+}
+
+/// parse a NAME=VALUE environment variable assignment
+fn parse_env_assignment(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((name, value)) if !name.is_empty() => Ok((name.to_string(), value.to_string())),
+        _ => Err(format!("expected NAME=VALUE, got '{}'", s)),
+    }
 }
 
 #[derive(Subcommand)]
@@ -131,6 +647,11 @@ enum ViewCommands {
         /// (default: false)
         #[arg(long)]
         with_unmanaged: bool,
+
+        /// the order in which to list the views, when sorting by name unmanaged
+        /// workspaces are listed alongside the views, otherwise after them
+        #[arg(long, value_enum, default_value_t = ListSort::Name)]
+        sort: ListSort,
     },
 
     // rename the currently active view
@@ -138,56 +659,333 @@ enum ViewCommands {
         /// the new name of the view
         new_name: String,
     },
+
+    /// add a command that is launched when the currently active view is
+    /// focused while its workspace has no windows. `{project}` and `{view}`
+    /// are replaced by the project and view names.
+    AddCommand {
+        /// the command line, run by i3 through `exec`
+        command: String,
+    },
+
+    /// list the launch commands of the currently active view
+    ListCommands {},
+
+    /// remove all the launch commands of the currently active view
+    ClearCommands {},
+
+    /// save the window layout of the currently active view, it is restored
+    /// when the view is focused while its workspace has no windows
+    SaveLayout {},
+
+    /// append the saved layout to the currently active view, the placeholder
+    /// windows are filled by matching windows as they are opened
+    RestoreLayout {},
+
+    /// forget the saved layout of the currently active view
+    ClearLayout {},
+
+    /// list the windows on a view, with their con_id, class and title
+    Windows {
+        /// the view (default: the current view)
+        #[arg(long, value_name = "[PROJECT/]VIEW")]
+        view: Option<String>,
+    },
+}
+
+/// get the project of the focused workspace, if that workspace is managed by
+/// muxwm
+fn current_project(repo: &Repository, wm: &mut WindowManager) -> Result<Option<Project>> {
+    let current_workspace = wm
+        .get_active_workspace_name()
+        .context("getting active workspace")?;
+    if !is_window_manager_display_name(&current_workspace) {
+        return Ok(None);
+    }
+    repo.get_project_from_window_manager_display_name(&current_workspace)
+        .context("getting current project")
+}
+
+/// get the view of the focused workspace, if that workspace is managed by
+/// muxwm
+fn current_view(repo: &Repository, wm: &mut WindowManager) -> Result<Option<View>> {
+    let current_workspace = wm
+        .get_active_workspace_name()
+        .context("getting active workspace")?;
+    repo.get_view_for_workspace(&current_workspace)
+        .context("getting current view")
+}
+
+/// get the named project, or the project of the focused workspace when no
+/// name is given
+fn named_or_current_project(
+    repo: &Repository,
+    wm: &mut WindowManager,
+    name: Option<&str>,
+    name_match: NameMatch,
+) -> Result<Project> {
+    match name {
+        Some(name) => repo
+            .find_project_by_name(name, name_match)
+            .context("getting project")?
+            .ok_or_else(|| anyhow::anyhow!("no project found for name '{}'", name)),
+        None => {
+            let current_workspace = wm
+                .get_active_workspace_name()
+                .context("getting active workspace")?;
+            repo.get_project_from_window_manager_display_name(&current_workspace)?
+                .ok_or_else(|| {
+                    anyhow::anyhow!("no project found for display name '{}'", current_workspace)
+                })
+        }
+    }
+}
+
+/// the view named by `path`, given as `project/view` or as a view of the
+/// current project, or the view of the active workspace when no path is given
+fn named_or_current_view(
+    repo: &Repository,
+    wm: &mut WindowManager,
+    path: Option<&str>,
+    name_match: NameMatch,
+) -> Result<View> {
+    match path {
+        Some(path) => {
+            let current = match parse_view_path(path) {
+                (None, _) => current_project(repo, wm)?,
+                (Some(_), _) => None,
+            };
+            repo.find_view_by_path(path, current.as_ref(), name_match)
+                .context("getting view")?
+                .ok_or_else(|| anyhow::anyhow!("no view found for '{}'", path))
+        }
+        None => {
+            let current_workspace = wm
+                .get_active_workspace_name()
+                .context("getting active workspace")?;
+            repo.get_view_from_window_manager_display_name(&current_workspace)?
+                .ok_or_else(|| {
+                    anyhow::anyhow!("no view found for display name '{}'", current_workspace)
+                })
+        }
+    }
+}
+
+/// run the `pin_set` hooks for the global pin under the key, after a change
+/// to it is committed
+fn run_pin_set_hooks(repo: &Repository, config: &Config, key: &str) -> Result<()> {
+    let Some(pin) = repo.get_pin_by_key(key)? else {
+        return Ok(());
+    };
+    let workspace = match pin.pin_type() {
+        PinType::Workspace => pin.target().unwrap_or_default().to_string(),
+        _ => match repo.get_view_for_pin(&pin)? {
+            Some(view) => repo.get_window_manager_display_name(&view)?,
+            None => String::new(),
+        },
+    };
+    config.hooks.run(&Event::PinSet {
+        key,
+        pin_type: &pin.pin_type().to_string(),
+        workspace: &workspace,
+    });
+    Ok(())
+}
+
+/// list the windows that are on muxwm views, with their views
+fn managed_windows(repo: &Repository, wm: &mut WindowManager) -> Result<Vec<(View, Window)>> {
+    let mut windows = Vec::new();
+    for window in wm.list_windows().context("listing windows")? {
+        if let Some(view) = repo.get_view_for_workspace(window.workspace())? {
+            windows.push((view, window));
+        }
+    }
+    Ok(windows)
 }
 
-struct WindowManager {
-    connection: I3Connection,
+/// the top level directory of the git repository containing `dir`, if any
+fn find_git_root(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .find(|d| d.join(".git").exists())
+        .map(Path::to_path_buf)
 }
 
-impl WindowManager {
-    fn new() -> Result<Self> {
-        Ok(Self {
-            connection: I3Connection::connect().context("connecting to i3")?,
-        })
+/// the project name derived from a directory's name
+fn project_name_for_dir(dir: &Path) -> Result<String> {
+    let name = dir
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("cannot derive a project name from {}", dir.display()))?;
+    Ok(sanitize_project_name(&name.to_string_lossy()))
+}
+
+/// the name of the tmux session paired with the project
+fn tmux_session_name(repo: &Repository, proj: &Project) -> Result<String> {
+    Ok(repo
+        .get_project_tmux_session(proj)
+        .with_context(|| format!("getting tmux session of project '{}'", proj.name()))?
+        .unwrap_or_else(|| tmux::session_name_for(proj.name())))
+}
+
+/// create the project's tmux session, in the project's root directory, if the
+/// tmux integration is enabled and the session does not exist yet
+fn ensure_tmux_session(repo: &Repository, config: &Config, proj: &Project) -> Result<()> {
+    if !config.tmux.enabled {
+        return Ok(());
+    }
+
+    let session = tmux_session_name(repo, proj)?;
+    let root = repo
+        .get_project_root(proj)
+        .with_context(|| format!("getting root of project '{}'", proj.name()))?;
+    Tmux::new(config.tmux.socket_name.clone())
+        .ensure_session(&session, root.as_deref())
+        .with_context(|| format!("creating tmux session '{}'", session))
+}
+
+/// create a project, with the views of the named template if one is given and
+/// with its root directory set if one is given
+fn create_project(
+    repo: &mut Repository,
+    config: &Config,
+    name: &str,
+    template: Option<&str>,
+    root: Option<&str>,
+) -> Result<Project> {
+    let proj = match template {
+        Some(template) => {
+            let t = config.template(template)?;
+            repo.create_project_with_views(name, &t.views, t.active.as_deref())
+                .with_context(|| {
+                    format!("creating project '{}' from template '{}'", name, template)
+                })?
+        }
+        None => repo
+            .create_project(name)
+            .with_context(|| format!("creating project '{}'", name))?,
+    };
+    if root.is_some() {
+        repo.set_project_root(&proj, root)
+            .with_context(|| format!("setting root of project '{}'", proj.name()))?;
     }
 
-    fn focus(&mut self, workspace: &str) -> Result<()> {
-        let cmd = format!("workspace {}", workspace);
-        self.connection
-            .run_command(&cmd)
-            .with_context(|| format!("running `workspace` command with {}", workspace))?;
-        Ok(())
+    config.hooks.run(&Event::ProjectCreated {
+        project: proj.name(),
+    });
+    Ok(proj)
+}
+
+/// ask a yes/no question on the terminal, anything but yes is a no
+fn confirm(question: &str) -> Result<bool> {
+    eprint!("{} [y/N] ", question);
+    let mut answer = String::new();
+    std::io::stdin()
+        .read_line(&mut answer)
+        .context("reading the answer")?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+/// the project named `name`, for commands that cannot be taken back: a name
+/// that only matches fuzzily must be confirmed first. `None` is returned when
+/// it was not.
+fn confirmed_project(
+    repo: &Repository,
+    name: &str,
+    name_match: NameMatch,
+) -> Result<Option<Project>> {
+    let proj = repo
+        .find_project_by_name(name, name_match)
+        .context("getting project")?
+        .ok_or_else(|| anyhow::anyhow!("no project found for name '{}'", name))?;
+    if proj.name() != name
+        && !confirm(&format!(
+            "'{}' matches project '{}', continue?",
+            name,
+            proj.name()
+        ))?
+    {
+        return Ok(None);
     }
+    Ok(Some(proj))
+}
 
-    fn get_active_workspace_name(&mut self) -> Result<String> {
-        let result = self.connection.get_workspaces()?;
-        result
-            .workspaces
-            .iter()
-            .find(|w| w.focused)
-            .map(|w| w.name.clone())
-            .ok_or(anyhow::anyhow!("no active workspace"))
+/// focus what a pin key resolved to: a view the way `focus_view` does, a
+/// workspace or window outside of muxwm directly
+fn focus_pin_target(
+    repo: &mut Repository,
+    wm: &mut WindowManager,
+    config: &Config,
+    target: &PinTarget,
+) -> Result<()> {
+    match target {
+        PinTarget::View(view) => focus_view(repo, wm, config, view),
+        PinTarget::Workspace(name) => wm
+            .focus(name)
+            .with_context(|| format!("focusing on workspace '{}'", name)),
+        PinTarget::Window(con_id) => wm.focus_window(*con_id),
+        PinTarget::Mark(mark) => wm.focus_mark(mark),
     }
+}
 
-    fn get_workspace_names(&mut self) -> Result<Vec<String>> {
-        let result = self
-            .connection
-            .get_workspaces()
-            .context("getting workspaces")?;
-        Ok(result.workspaces.iter().map(|w| w.name.clone()).collect())
+/// focus the workspace of the given view and record that it was focused
+fn focus_view(
+    repo: &mut Repository,
+    wm: &mut WindowManager,
+    config: &Config,
+    view: &View,
+) -> Result<()> {
+    let display_name = repo
+        .get_window_manager_display_name(view)
+        .with_context(|| format!("getting display name for view '{}'", view.name()))?;
+    let previous_project = current_project(repo, wm)?;
+    wm.focus(&display_name)
+        .with_context(|| format!("focusing on workspace '{}'", display_name))?;
+    repo.mark_view_focused(view)
+        .with_context(|| format!("recording focus of workspace '{}'", display_name))?;
+
+    // populate an empty workspace with the view's saved layout and launch
+    // commands, the layout first so that its placeholders swallow the
+    // launched windows
+    let layout = repo
+        .get_view_layout(view)
+        .with_context(|| format!("getting layout for view '{}'", view.name()))?;
+    let commands = repo
+        .get_launch_commands_for_view(view)
+        .with_context(|| format!("getting launch commands for view '{}'", view.name()))?;
+    if (layout.is_some() || !commands.is_empty())
+        && !wm
+            .workspace_has_windows(&display_name)
+            .with_context(|| format!("looking for windows on workspace '{}'", display_name))?
+    {
+        if let Some(layout) = layout {
+            wm.append_layout(&layout)
+                .with_context(|| format!("restoring layout of workspace '{}'", display_name))?;
+        }
+        for command in &commands {
+            wm.exec(command)
+                .with_context(|| format!("launching '{}'", command))?;
+        }
     }
 
-    fn rename_workspace(&mut self, old_name: &str, new_name: &str) -> Result<()> {
-        let cmd = format!("rename workspace \"{}\" to \"{}\"", old_name, new_name);
-        self.connection
-            .run_command(&cmd)
-            .context("renameing workspace")?;
-        Ok(())
+    let proj = repo
+        .get_project_from_window_manager_display_name(&display_name)?
+        .ok_or_else(|| anyhow::anyhow!("no project found for display name '{}'", display_name))?;
+    if previous_project.is_none_or(|p| p.name() != proj.name()) {
+        config.hooks.run(&Event::ProjectFocused {
+            project: proj.name(),
+            workspace: &display_name,
+        });
     }
+    config.hooks.run(&Event::ViewFocused {
+        project: proj.name(),
+        view: view.name(),
+        workspace: &display_name,
+    });
+    Ok(())
 }
 
 fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
+    let config = Config::load(cli.config.as_deref()).context("loading config")?;
     let mut i3 = WindowManager::new().context("creating window manager")?;
 
     // Check how many times the debug flag occurred for verbosity
@@ -203,55 +1001,211 @@ fn main() -> anyhow::Result<()> {
     let conn = Connection::open(&db_path)
         .with_context(|| format!("opening database at {}", db_path.display()))?;
     let mut repo = Repository::new(conn).context("creating repository")?;
+    let name_match = if cli.exact {
+        NameMatch::Exact
+    } else {
+        NameMatch::Fuzzy
+    };
 
     match &cli.command {
         Commands::Pin { command } => match command {
             PinCommands::Focus { key } => {
-                let view = repo
-                    .get_view_for_pin_key(key)
-                    .context("getting view for pin key")?
-                    .ok_or_else(|| anyhow::anyhow!("no view found for pin key '{}'", key))?;
-                let display_name = repo
-                    .get_window_manager_display_name(&view)
-                    .context("getting display name for view")?;
-                i3.focus(&display_name)
-                    .with_context(|| format!("focusing on workspace '{}'", display_name))?;
-            }
-
-            PinCommands::Set { key, project } => {
-                let name = i3
-                    .get_active_workspace_name()
-                    .context("getting active workspace")?;
-                if *project {
+                let current = current_view(&repo, &mut i3)?;
+                let target = repo
+                    .get_pin_target_for_key(key, current.as_ref())
+                    .context("getting target for pin key")?
+                    .ok_or_else(|| anyhow::anyhow!("nothing found for pin key '{}'", key))?;
+                focus_pin_target(&mut repo, &mut i3, &config, &target)?;
+            }
+
+            PinCommands::Set {
+                key,
+                project,
+                view,
+                local,
+                workspace,
+                window,
+                mark,
+                force,
+            } => {
+                let hint = || {
+                    format!(
+                        "setting pin '{}' (use --force to repoint an existing pin)",
+                        key
+                    )
+                };
+                // the workspace is the one the pin focuses, for the hooks
+                let external = if let Some(name) = workspace {
+                    let name = match name {
+                        Some(name) => name.clone(),
+                        None => i3
+                            .get_active_workspace_name()
+                            .context("getting active workspace")?,
+                    };
+                    if is_window_manager_display_name(&name) {
+                        return Err(anyhow::anyhow!(
+                            "workspace '{}' is managed by muxwm, pin its view with \
+                             `muxwm pin set {} --view {}` instead",
+                            name,
+                            key,
+                            name.replacen('#', "/", 1)
+                        ));
+                    }
+                    Some((PinType::Workspace, name))
+                } else if let Some(con_id) = window {
+                    let con_id = match con_id {
+                        Some(con_id) => *con_id,
+                        None => i3
+                            .get_focused_window_id()
+                            .context("getting focused window")?,
+                    };
+                    Some((PinType::Window, con_id.to_string()))
+                } else {
+                    mark.as_ref().map(|mark| (PinType::Mark, mark.clone()))
+                };
+
+                let (pin_type, target, workspace, change) = if let Some((pin_type, target)) =
+                    external
+                {
+                    let change = repo
+                        .upsert_external_pin(key, pin_type, &target, *force)
+                        .with_context(hint)?;
+                    // windows move between workspaces, so only workspace pins
+                    // name one for the hooks
+                    let workspace = match pin_type {
+                        PinType::Workspace => target.clone(),
+                        _ => String::new(),
+                    };
+                    (pin_type, target, workspace, change)
+                } else if let Some(project_name) = project {
+                    let proj = named_or_current_project(
+                        &repo,
+                        &mut i3,
+                        project_name.as_deref(),
+                        name_match,
+                    )?;
+                    let workspace = repo.get_window_manager_display_name(
+                        &repo.get_active_view_for_project(&proj)?,
+                    )?;
+                    let change = repo
+                        .upsert_pin_for_project(key, &proj, *force)
+                        .with_context(hint)?;
+                    (PinType::Project, proj.name().to_string(), workspace, change)
+                } else {
+                    let view = named_or_current_view(&repo, &mut i3, view.as_deref(), name_match)?;
+                    let target = repo.get_window_manager_display_name(&view)?;
+                    if *local {
+                        let change = repo
+                            .upsert_local_pin_for_view(key, &view, *force)
+                            .with_context(hint)?;
+                        (PinType::Local, target.clone(), target, change)
+                    } else {
+                        let change = repo
+                            .upsert_pin_for_view(key, &view, *force)
+                            .with_context(hint)?;
+                        (PinType::View, target.clone(), target, change)
+                    }
+                };
+
+                let Some(change) = change else {
+                    println!("{} pin '{}' already points to {}", pin_type, key, target);
+                    return Ok(());
+                };
+                println!("{} pin '{}' now points to {}", pin_type, key, target);
+                if let Some(previous) = change.replaced() {
+                    println!("  it pointed to {} before", previous);
+                }
+                if let Some(old_key) = change.moved_from() {
+                    println!("  removed the previous pin '{}' of {}", old_key, target);
+                }
+                config.hooks.run(&Event::PinSet {
+                    key,
+                    pin_type: &pin_type.to_string(),
+                    workspace: &workspace,
+                });
+            }
+
+            PinCommands::Clear { key, local } => {
+                if *local {
+                    let current_workspace = i3
+                        .get_active_workspace_name()
+                        .context("getting active workspace")?;
                     let proj = repo
-                        .get_project_from_window_manager_display_name(&name)?
+                        .get_project_from_window_manager_display_name(&current_workspace)?
                         .ok_or_else(|| {
-                            anyhow::anyhow!("no project found for display name '{}'", name)
+                            anyhow::anyhow!(
+                                "no project found for display name '{}'",
+                                current_workspace
+                            )
                         })?;
-                    repo.upsert_pin_for_project(key, &proj)
-                        .with_context(|| format!("upserting pin for project '{}'", proj.name()))?;
+                    repo.clear_local_pin(&proj, key).with_context(|| {
+                        format!("clearing local pin '{}' of project '{}'", key, proj.name())
+                    })?;
                 } else {
-                    let view = repo
-                        .get_view_from_window_manager_display_name(&name)?
-                        .ok_or_else(|| {
-                            anyhow::anyhow!("no view found for display name '{}'", name)
-                        })?;
-                    repo.upsert_pin_for_view(key, &view)
-                        .with_context(|| format!("upserting pin '{}' for view '{}'", key, name))?;
+                    repo.clear_pin(key)
+                        .with_context(|| format!("clearing pin '{}'", key))?;
+                }
+            }
+
+            PinCommands::Add { key, view } => {
+                let view = named_or_current_view(&repo, &mut i3, view.as_deref(), name_match)?;
+                let target = repo.get_window_manager_display_name(&view)?;
+                repo.add_view_to_pin_stack(key, &view)
+                    .with_context(|| format!("adding {} to pin stack '{}'", target, key))?;
+                println!("added {} to pin stack '{}'", target, key);
+                config.hooks.run(&Event::PinSet {
+                    key,
+                    pin_type: &PinType::Stack.to_string(),
+                    workspace: &target,
+                });
+            }
+
+            PinCommands::Remove { key, view } => {
+                let view = named_or_current_view(&repo, &mut i3, view.as_deref(), name_match)?;
+                let target = repo.get_window_manager_display_name(&view)?;
+                repo.remove_view_from_pin_stack(key, &view)
+                    .with_context(|| format!("removing {} from pin stack '{}'", target, key))?;
+                println!("removed {} from pin stack '{}'", target, key);
+            }
+
+            PinCommands::Swap { a, b } => {
+                repo.swap_pins(a, b)
+                    .with_context(|| format!("swapping pins '{}' and '{}'", a, b))?;
+                println!("swapped pins '{}' and '{}'", a, b);
+                if a != b {
+                    run_pin_set_hooks(&repo, &config, a)?;
+                    run_pin_set_hooks(&repo, &config, b)?;
                 }
             }
 
-            PinCommands::Clear { key } => {
-                repo.clear_pin(&key)
-                    .with_context(|| format!("clearing pin '{}'", key))?;
+            PinCommands::Rename { old, new } => {
+                repo.rename_pin(old, new)
+                    .with_context(|| format!("renaming pin '{}' to '{}'", old, new))?;
+                println!("renamed pin '{}' to '{}'", old, new);
+                run_pin_set_hooks(&repo, &config, new)?;
             }
 
             PinCommands::List {} => {
                 let pins = repo.list_pins().context("listing pins")?;
                 for pin in pins {
                     let k = pin.key();
+                    if pin.pin_type() == PinType::Stack {
+                        let names = repo
+                            .list_pin_stack_views(&pin)
+                            .with_context(|| format!("getting views of pin stack '{}'", k))?
+                            .iter()
+                            .map(|view| repo.get_window_manager_display_name(view))
+                            .collect::<Result<Vec<_>>>()
+                            .context("getting display name for view")?;
+                        println!("{}\t{}\t{}", k, pin.pin_type(), names.join(" "));
+                        continue;
+                    }
+                    if let Some(target) = pin.target() {
+                        println!("{}\t{}\t{}", k, pin.pin_type(), target);
+                        continue;
+                    }
                     let view = repo
-                        .get_view_for_pin_key(&k)
+                        .get_view_for_pin(&pin)
                         .with_context(|| format!("getting view for pin '{}'", k))?
                         .ok_or_else(|| anyhow::anyhow!("no view found for pin key '{}'", k))?;
 
@@ -264,13 +1218,46 @@ fn main() -> anyhow::Result<()> {
         },
 
         Commands::Project { command } => match command {
-            ProjectCommands::Add { name } => {
-                repo.create_project(&name)
-                    .with_context(|| format!("creating project '{}'", name))?;
+            ProjectCommands::Add {
+                name,
+                focus,
+                template,
+            } => {
+                let proj = create_project(&mut repo, &config, name, template.as_deref(), None)?;
+                if *focus {
+                    let view = repo
+                        .get_active_view_for_project(&proj)
+                        .context("getting active view for project")?;
+                    focus_view(&mut repo, &mut i3, &config, &view)?;
+                }
             }
 
-            ProjectCommands::List { with_pins } => {
-                let projects = repo.list_projects().context("listing projects")?;
+            ProjectCommands::Open { name, template } => {
+                let template = template
+                    .as_deref()
+                    .map(|t| config.template(t))
+                    .transpose()?;
+                let (proj, view, created) = repo
+                    .open_project(
+                        name,
+                        name_match,
+                        template.map(|t| t.views.as_slice()),
+                        template.and_then(|t| t.active.as_deref()),
+                    )
+                    .with_context(|| format!("opening project '{}'", name))?;
+                if created {
+                    config.hooks.run(&Event::ProjectCreated {
+                        project: proj.name(),
+                    });
+                }
+                ensure_tmux_session(&repo, &config, &proj)?;
+                focus_view(&mut repo, &mut i3, &config, &view)?;
+            }
+
+            ProjectCommands::List { with_pins, sort } => {
+                let projects = repo
+                    .list_projects((*sort).into())
+                    .context("listing projects")?;
                 for proj in projects {
                     let mut pin_key = String::default();
                     if *with_pins {
@@ -286,17 +1273,98 @@ fn main() -> anyhow::Result<()> {
 
             ProjectCommands::Focus { name } => {
                 let proj = repo
-                    .get_project_by_name(&name)
+                    .find_project_by_name(name, name_match)
                     .context("getting project")?
                     .ok_or_else(|| anyhow::anyhow!("no project found for name '{}'", name))?;
+                ensure_tmux_session(&repo, &config, &proj)?;
                 let view = repo
                     .get_active_view_for_project(&proj)
                     .context("getting active view for project")?;
-                let display_name = repo
-                    .get_window_manager_display_name(&view)
-                    .context("getting display name for active view for project")?;
-                i3.focus(&display_name)
-                    .with_context(|| format!("focusing on workspace '{}'", display_name))?;
+                focus_view(&mut repo, &mut i3, &config, &view)?;
+            }
+
+            ProjectCommands::Remove {
+                name,
+                kill_tmux_session,
+            } => {
+                let Some(proj) = confirmed_project(&repo, name, name_match)? else {
+                    return Ok(());
+                };
+                let session = tmux_session_name(&repo, &proj)?;
+                repo.delete_project(&proj)
+                    .with_context(|| format!("removing project '{}'", proj.name()))?;
+
+                if *kill_tmux_session {
+                    let tmux = Tmux::new(config.tmux.socket_name.clone());
+                    if tmux
+                        .has_session(&session)
+                        .with_context(|| format!("looking for tmux session '{}'", session))?
+                    {
+                        tmux.kill_session(&session)
+                            .with_context(|| format!("killing tmux session '{}'", session))?;
+                    }
+                }
+            }
+
+            ProjectCommands::Close { name, yes, dry_run } => {
+                // nothing is killed on a dry run, so the match needs no
+                // confirmation then
+                let proj = if *dry_run {
+                    repo.find_project_by_name(name, name_match)
+                        .context("getting project")?
+                        .ok_or_else(|| anyhow::anyhow!("no project found for name '{}'", name))?
+                } else {
+                    let Some(proj) = confirmed_project(&repo, name, name_match)? else {
+                        return Ok(());
+                    };
+                    proj
+                };
+                let views = repo
+                    .list_views_for_project(&proj, SortOrder::Position)
+                    .with_context(|| format!("listing views of project '{}'", proj.name()))?;
+                let workspaces = views
+                    .iter()
+                    .map(|v| repo.get_window_manager_display_name(v))
+                    .collect::<Result<Vec<_>>>()?;
+                let plan = i3
+                    .plan_close_windows(&workspaces)
+                    .context("listing windows")?;
+                if plan.windows().is_empty() {
+                    println!("project '{}' has no windows", proj.name());
+                    return Ok(());
+                }
+                for window in plan.windows() {
+                    let view = workspaces
+                        .iter()
+                        .position(|w| w == window.workspace())
+                        .map(|i| views[i].name())
+                        .unwrap_or_default();
+                    println!(
+                        "{}\t{}\t{}\t{}",
+                        window.con_id(),
+                        view,
+                        window.class().unwrap_or_default(),
+                        window.title().unwrap_or_default()
+                    );
+                }
+
+                let question = format!(
+                    "kill {} window(s) of project '{}'?",
+                    plan.windows().len(),
+                    proj.name()
+                );
+                if *dry_run || !(*yes || confirm(&question)?) {
+                    return Ok(());
+                }
+                i3.close_windows(&plan)?;
+            }
+
+            ProjectCommands::SetTmuxSession { session } => {
+                let proj = named_or_current_project(&repo, &mut i3, None, name_match)?;
+                repo.set_project_tmux_session(&proj, session.as_deref())
+                    .with_context(|| {
+                        format!("setting tmux session of project '{}'", proj.name())
+                    })?;
             }
 
             ProjectCommands::ActivateNextView {} => {
@@ -313,17 +1381,7 @@ fn main() -> anyhow::Result<()> {
                     .with_context(|| format!("getting next view for project '{}'", proj.name()))?;
                 repo.set_active_view_for_project(&proj, &next)
                     .with_context(|| format!("setting view for project '{}'", proj.name()))?;
-                let next_workspace =
-                    repo.get_window_manager_display_name(&next)
-                        .with_context(|| {
-                            format!(
-                                "getting display name for project '{}' view '{}'",
-                                proj.name(),
-                                next.name()
-                            )
-                        })?;
-                i3.focus(&next_workspace)
-                    .with_context(|| format!("focusing on workspace '{}'", next_workspace))?;
+                focus_view(&mut repo, &mut i3, &config, &next)?;
             }
 
             ProjectCommands::ActivatePrevView {} => {
@@ -340,20 +1398,70 @@ fn main() -> anyhow::Result<()> {
                     .with_context(|| format!("getting prev view for project '{}'", proj.name()))?;
                 repo.set_active_view_for_project(&proj, &prev)
                     .with_context(|| format!("setting view for project '{}'", proj.name()))?;
-                let previous_workspace =
-                    repo.get_window_manager_display_name(&prev)
+                focus_view(&mut repo, &mut i3, &config, &prev)?;
+            }
+
+            ProjectCommands::FocusView {
+                index,
+                name,
+                create,
+            } => {
+                let current_workspace = i3
+                    .get_active_workspace_name()
+                    .context("getting active workspace")?;
+                let proj = repo
+                    .get_project_from_window_manager_display_name(&current_workspace)?
+                    .ok_or_else(|| {
+                        anyhow::anyhow!("no project found for display name '{}'", current_workspace)
+                    })?;
+
+                let view = match (index, name) {
+                    (Some(index), _) => repo
+                        .get_view_at_index_in_project(&proj, *index)
                         .with_context(|| {
-                            format!(
-                                "getting display name for project '{}' view '{}'",
-                                proj.name(),
-                                prev.name()
-                            )
-                        })?;
-                i3.focus(&previous_workspace)
-                    .with_context(|| format!("focusing on workspace '{}'", previous_workspace))?;
+                            format!("getting view {} of project '{}'", index, proj.name())
+                        })?
+                        .ok_or_else(|| {
+                            anyhow::anyhow!("project '{}' has no view {}", proj.name(), index)
+                        })?,
+                    (None, Some(name)) => {
+                        // when creating, a fuzzy match must not stand in for
+                        // the view that was asked for
+                        let mode = if *create {
+                            NameMatch::Exact
+                        } else {
+                            name_match
+                        };
+                        let found = repo
+                            .find_view_in_project_by_name(&proj, name, mode)
+                            .with_context(|| {
+                                format!("getting view '{}' of project '{}'", name, proj.name())
+                            })?;
+                        match found {
+                            Some(view) => view,
+                            None if *create => {
+                                repo.create_view_in_project(&proj, name).with_context(|| {
+                                    format!("creating view for project '{}'", proj.name())
+                                })?
+                            }
+                            None => {
+                                return Err(anyhow::anyhow!(
+                                    "project '{}' has no view '{}'",
+                                    proj.name(),
+                                    name
+                                ));
+                            }
+                        }
+                    }
+                    (None, None) => unreachable!("clap requires an index or a name"),
+                };
+
+                repo.set_active_view_for_project(&proj, &view)
+                    .with_context(|| format!("setting view for project '{}'", proj.name()))?;
+                focus_view(&mut repo, &mut i3, &config, &view)?;
             }
 
-            ProjectCommands::AddView { view_name } => {
+            ProjectCommands::AddView { view_name, focus } => {
                 let display_name = i3
                     .get_active_workspace_name()
                     .context("getting active workspace")?;
@@ -362,11 +1470,192 @@ fn main() -> anyhow::Result<()> {
                     .ok_or_else(|| {
                         anyhow::anyhow!("no project found for display name '{}'", display_name)
                     })?;
-                repo.create_view_in_project(&proj, &view_name)
+                let view = repo
+                    .create_view_in_project(&proj, view_name)
                     .with_context(|| format!("creating view for project '{}'", proj.name()))?;
+                if *focus {
+                    repo.set_active_view_for_project(&proj, &view)
+                        .with_context(|| format!("setting view for project '{}'", proj.name()))?;
+                    focus_view(&mut repo, &mut i3, &config, &view)?;
+                }
+            }
+
+            ProjectCommands::FromDir {
+                path: None,
+                scan: Some(scan),
+                ..
+            } => {
+                let mut candidates = Vec::new();
+                for entry in std::fs::read_dir(scan)
+                    .with_context(|| format!("reading directory {}", scan.display()))?
+                {
+                    let dir = entry
+                        .with_context(|| format!("reading directory {}", scan.display()))?
+                        .path();
+                    if !dir.join(".git").exists() {
+                        continue;
+                    }
+                    let dir = dir
+                        .canonicalize()
+                        .with_context(|| format!("resolving {}", dir.display()))?;
+                    let name = project_name_for_dir(&dir)?;
+                    let registered = repo
+                        .get_project_by_root(&dir.to_string_lossy())
+                        .context("getting project by root")?
+                        .is_some()
+                        // only an exact name is taken, any other project
+                        // name can still be created
+                        || repo
+                            .find_project_by_name(&name, NameMatch::Exact)
+                            .context("getting project")?
+                            .is_some();
+                    if !registered {
+                        candidates.push((dir, name));
+                    }
+                }
+
+                candidates.sort();
+                for (dir, name) in candidates {
+                    println!("{}\t{}", dir.display(), name);
+                }
+            }
+
+            ProjectCommands::FromDir {
+                path,
+                name,
+                template,
+                focus,
+                ..
+            } => {
+                let path = path
+                    .as_ref()
+                    .expect("INTERNAL ERROR: clap requires a path without --scan");
+                let dir = path
+                    .canonicalize()
+                    .with_context(|| format!("resolving {}", path.display()))?;
+                let dir = find_git_root(&dir).unwrap_or(dir);
+                let root = dir.to_string_lossy();
+
+                if let Some(existing) = repo
+                    .get_project_by_root(&root)
+                    .context("getting project by root")?
+                {
+                    return Err(anyhow::anyhow!(
+                        "{} is already the root of project '{}'",
+                        root,
+                        existing.name()
+                    ));
+                }
+
+                let name = match name {
+                    Some(name) => name.clone(),
+                    None => project_name_for_dir(&dir)?,
+                };
+                let proj =
+                    create_project(&mut repo, &config, &name, template.as_deref(), Some(&root))?;
+                if *focus {
+                    let view = repo
+                        .get_active_view_for_project(&proj)
+                        .context("getting active view for project")?;
+                    focus_view(&mut repo, &mut i3, &config, &view)?;
+                }
+            }
+
+            ProjectCommands::SetRoot { dir } => {
+                let proj = named_or_current_project(&repo, &mut i3, None, name_match)?;
+                let root = match dir {
+                    Some(dir) => Some(
+                        dir.canonicalize()
+                            .with_context(|| format!("resolving {}", dir.display()))?
+                            .to_string_lossy()
+                            .into_owned(),
+                    ),
+                    None => None,
+                };
+                repo.set_project_root(&proj, root.as_deref())
+                    .with_context(|| format!("setting root of project '{}'", proj.name()))?;
             }
 
-            ProjectCommands::ListViews {} => {
+            ProjectCommands::SetEnv { assignment } => {
+                let proj = named_or_current_project(&repo, &mut i3, None, name_match)?;
+                let (name, value) = assignment;
+                repo.set_project_env(&proj, name, value).with_context(|| {
+                    format!("setting variable '{}' of project '{}'", name, proj.name())
+                })?;
+            }
+
+            ProjectCommands::UnsetEnv { name } => {
+                let proj = named_or_current_project(&repo, &mut i3, None, name_match)?;
+                repo.unset_project_env(&proj, name).with_context(|| {
+                    format!("unsetting variable '{}' of project '{}'", name, proj.name())
+                })?;
+            }
+
+            ProjectCommands::Env {} => {
+                let proj = named_or_current_project(&repo, &mut i3, None, name_match)?;
+                for (name, value) in repo
+                    .list_project_env(&proj)
+                    .with_context(|| format!("listing variables of project '{}'", proj.name()))?
+                {
+                    println!("{}={}", name, value);
+                }
+            }
+
+            ProjectCommands::Cwd { project } => {
+                let proj =
+                    named_or_current_project(&repo, &mut i3, project.as_deref(), name_match)?;
+                let root = repo
+                    .get_project_root(&proj)
+                    .with_context(|| format!("getting root of project '{}'", proj.name()))?
+                    .ok_or_else(|| {
+                        anyhow::anyhow!("project '{}' has no root directory", proj.name())
+                    })?;
+                println!("{}", root);
+            }
+
+            ProjectCommands::Windows { project } => {
+                let proj =
+                    named_or_current_project(&repo, &mut i3, project.as_deref(), name_match)?;
+                for (view, window) in managed_windows(&repo, &mut i3)? {
+                    if proj.contains_view(&view) {
+                        println!(
+                            "{}\t{}\t{}\t{}",
+                            window.con_id(),
+                            view.name(),
+                            window.class().unwrap_or_default(),
+                            window.title().unwrap_or_default()
+                        );
+                    }
+                }
+            }
+
+            ProjectCommands::Exec { project, command } => {
+                let proj =
+                    named_or_current_project(&repo, &mut i3, project.as_deref(), name_match)?;
+                let (program, args) = command
+                    .split_first()
+                    .expect("INTERNAL ERROR: clap requires a command");
+
+                let mut cmd = std::process::Command::new(program);
+                cmd.args(args).env("MUXWM_PROJECT", proj.name()).envs(
+                    repo.list_project_env(&proj).with_context(|| {
+                        format!("listing variables of project '{}'", proj.name())
+                    })?,
+                );
+                if let Some(root) = repo
+                    .get_project_root(&proj)
+                    .with_context(|| format!("getting root of project '{}'", proj.name()))?
+                {
+                    cmd.current_dir(root);
+                }
+
+                // replace muxwm with the command, so that its exit status and
+                // signals are the command's own
+                let err = cmd.exec();
+                return Err(err).with_context(|| format!("running '{}'", program));
+            }
+
+            ProjectCommands::ListViews { sort } => {
                 let current_workspace = i3
                     .get_active_workspace_name()
                     .context("getting active workspace")?;
@@ -376,7 +1665,7 @@ fn main() -> anyhow::Result<()> {
                         anyhow::anyhow!("no project found for display name '{}'", current_workspace)
                     })?;
                 let views = repo
-                    .list_views_for_project(&proj)
+                    .list_views_for_project(&proj, (*sort).into())
                     .with_context(|| format!("listing views for project '{}'", proj.name()))?;
                 for view in views {
                     let display_name =
@@ -393,6 +1682,396 @@ fn main() -> anyhow::Result<()> {
             }
         },
 
+        Commands::Export { file, format } => {
+            let format = format
+                .map(Into::into)
+                .or_else(|| file.as_deref().and_then(state::Format::for_path))
+                .unwrap_or(state::Format::Json);
+            let contents = repo
+                .export_state()
+                .context("exporting state")?
+                .to_string(format)
+                .context("serializing state")?;
+            match file {
+                Some(file) => std::fs::write(file, contents)
+                    .with_context(|| format!("writing {}", file.display()))?,
+                None => print!("{}", contents),
+            }
+        }
+
+        Commands::Import {
+            file,
+            format,
+            mode,
+            dry_run,
+        } => {
+            let format = format
+                .map(Into::into)
+                .or_else(|| state::Format::for_path(file))
+                .unwrap_or(state::Format::Json);
+            let contents = std::fs::read_to_string(file)
+                .with_context(|| format!("reading {}", file.display()))?;
+            let state = state::State::parse(&contents, format)
+                .with_context(|| format!("parsing {}", file.display()))?;
+            let mode = (*mode).into();
+
+            if *dry_run {
+                if mode == ImportMode::Replace {
+                    for proj in repo.list_projects(SortOrder::Created)? {
+                        println!("remove project {}", proj.name());
+                    }
+                }
+                for proj in &state.projects {
+                    println!(
+                        "create project {} with {} view(s)",
+                        proj.name,
+                        proj.views.len()
+                    );
+                }
+                for pin in &state.pins {
+                    match &pin.view {
+                        Some(view) => {
+                            println!(
+                                "create {} pin {} -> {}/{}",
+                                pin.pin_type, pin.key, pin.project, view
+                            )
+                        }
+                        None => {
+                            println!("create {} pin {} -> {}", pin.pin_type, pin.key, pin.project)
+                        }
+                    }
+                }
+                for pin in &state.external_pins {
+                    println!("create {} pin {} -> {}", pin.pin_type, pin.key, pin.target);
+                }
+                for stack in &state.stacks {
+                    println!(
+                        "create pin stack {} with {} view(s)",
+                        stack.key,
+                        stack.views.len()
+                    );
+                }
+                for conflict in repo.import_conflicts(&state, mode)? {
+                    println!("conflict: {}", conflict);
+                }
+            } else {
+                repo.import_state(&state, mode)
+                    .with_context(|| format!("importing {}", file.display()))?;
+            }
+        }
+
+        Commands::Sync {
+            manifest: manifest_path,
+            yes,
+            dry_run,
+        } => {
+            let manifest_path = match manifest_path {
+                Some(path) => path.clone(),
+                None => config::config_dir()
+                    .ok_or_else(|| anyhow::anyhow!("no config directory, pass --manifest"))?
+                    .join("manifest.toml"),
+            };
+            let manifest = manifest::Manifest::read(&manifest_path)?;
+            let changes = manifest::plan(&repo, &manifest).context("planning sync")?;
+            if changes.is_empty() {
+                println!("nothing to change");
+                return Ok(());
+            }
+            for change in &changes {
+                println!("{}", change);
+            }
+            if *dry_run || !(*yes || confirm(&format!("apply {} change(s)?", changes.len()))?) {
+                return Ok(());
+            }
+
+            manifest::apply(&mut repo, &changes)?;
+
+            // keep the workspaces of renamed views, and run the hooks the
+            // equivalent commands would run
+            let workspaces = i3.get_workspace_names().context("getting workspaces")?;
+            for change in &changes {
+                match change {
+                    manifest::Change::CreateProject { name, .. } => {
+                        config.hooks.run(&Event::ProjectCreated { project: name });
+                    }
+                    manifest::Change::RenameView { project, from, to } => {
+                        let old_display_name = model::window_manager_display_name(project, from);
+                        let new_display_name = model::window_manager_display_name(project, to);
+                        if workspaces.contains(&old_display_name) {
+                            i3.rename_workspace(&old_display_name, &new_display_name)?;
+                        }
+                        config.hooks.run(&Event::ViewRenamed {
+                            project,
+                            old_view: from,
+                            view: to,
+                            workspace: &new_display_name,
+                        });
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        Commands::Undo { count } => {
+            for _ in 0..*count {
+                let Some(undo) = repo.undo().context("undoing")? else {
+                    println!("nothing left to undo");
+                    break;
+                };
+                let workspaces = i3.get_workspace_names().context("getting workspaces")?;
+                for (from, to) in undo.workspace_renames() {
+                    if workspaces.contains(from) {
+                        i3.rename_workspace(from, to)?;
+                    }
+                }
+                println!("undid: {}", undo.description());
+            }
+        }
+
+        Commands::History { limit } => {
+            for entry in repo.list_journal(*limit).context("listing history")? {
+                println!(
+                    "{}\t{}\t{}{}",
+                    entry.id(),
+                    entry.at(),
+                    entry.description(),
+                    if entry.is_undone() { " (undone)" } else { "" }
+                );
+            }
+        }
+
+        Commands::Report { since, by, format } => {
+            let since = match since {
+                Some(date) => repo.timestamp_for_date(date)?,
+                None => 0,
+            };
+            let until = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .context("getting the current time")?
+                .as_secs() as i64;
+            let totals = repo
+                .focus_totals(since, until, (*by).into())
+                .context("totalling focus time")?;
+
+            let mut out = std::io::stdout().lock();
+            match format {
+                ReportFormat::Table => report::write_table(&mut out, &totals)?,
+                ReportFormat::Csv => report::write_csv(&mut out, &totals, *by == ReportBy::View)?,
+                ReportFormat::Json => report::write_json(&mut out, &totals)?,
+            }
+        }
+
+        Commands::Window { command } => match command {
+            WindowCommands::Which { con_id } => {
+                let con_id = match con_id {
+                    Some(con_id) => *con_id,
+                    None => i3
+                        .get_focused_window_id()
+                        .context("getting focused window")?,
+                };
+                let window = i3
+                    .list_windows()
+                    .context("listing windows")?
+                    .into_iter()
+                    .find(|w| w.con_id() == con_id)
+                    .ok_or_else(|| anyhow::anyhow!("no window with con_id {}", con_id))?;
+                let view = repo
+                    .get_view_for_workspace(window.workspace())?
+                    .ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "window {} is on workspace '{}', which is not a muxwm view",
+                            con_id,
+                            window.workspace()
+                        )
+                    })?;
+                let proj = repo
+                    .get_project_from_window_manager_display_name(window.workspace())?
+                    .ok_or_else(|| anyhow::anyhow!("project not found"))?;
+                println!("{}\t{}", proj.name(), view.name());
+            }
+
+            WindowCommands::Send {
+                view,
+                project,
+                pin,
+                follow,
+            } => {
+                let current = current_view(&repo, &mut i3)?;
+                let target = repo
+                    .find_target(
+                        view.as_deref(),
+                        project.as_deref(),
+                        pin.as_deref(),
+                        current.as_ref(),
+                        name_match,
+                    )
+                    .context("getting the target")?
+                    .ok_or_else(|| {
+                        let name = pin.as_ref().or(project.as_ref()).or(view.as_ref());
+                        anyhow::anyhow!("nothing found for '{}'", name.cloned().unwrap_or_default())
+                    })?;
+                let destination = match &target {
+                    PinTarget::View(view) => {
+                        Destination::Workspace(repo.get_window_manager_display_name(view)?)
+                    }
+                    PinTarget::Workspace(name) => Destination::Workspace(name.clone()),
+                    PinTarget::Window(con_id) => Destination::Window(*con_id),
+                    PinTarget::Mark(mark) => Destination::Mark(mark.clone()),
+                };
+
+                let con_id = i3
+                    .get_focused_window_id()
+                    .context("getting focused window")?;
+                match &target {
+                    // a view is followed the way `focus_view` focuses it, once
+                    // the window is on it and before the window is focused
+                    PinTarget::View(view) if *follow => {
+                        i3.send_window(con_id, &destination, false)?;
+                        focus_view(&mut repo, &mut i3, &config, view)?;
+                        i3.focus_window(con_id)?;
+                    }
+                    _ => i3.send_window(con_id, &destination, *follow)?,
+                }
+            }
+        },
+
+        Commands::Pick { target } => {
+            // the focused workspace may not be managed by muxwm, in which
+            // case nothing is marked as active
+            let current_workspace = i3
+                .get_active_workspace_name()
+                .context("getting active workspace")?;
+            let (current_view, current_proj) = if is_window_manager_display_name(&current_workspace)
+            {
+                (
+                    repo.get_view_from_window_manager_display_name(&current_workspace)
+                        .context("getting current view")?,
+                    repo.get_project_from_window_manager_display_name(&current_workspace)
+                        .context("getting current project")?,
+                )
+            } else {
+                (None, None)
+            };
+
+            match target {
+                PickTarget::Project => {
+                    let mut entries = Vec::new();
+                    for proj in repo
+                        .list_projects(SortOrder::MostRecentlyUsed)
+                        .context("listing projects")?
+                    {
+                        let mut entry = Entry::new(proj.name().to_string());
+                        if let Some(key) = repo
+                            .get_pin_key_for_project(&proj)
+                            .context("getting pin key for project")?
+                        {
+                            entry = entry.annotate(format!("[{}]", key));
+                        }
+                        if current_view.as_ref().is_some_and(|v| proj.contains_view(v)) {
+                            entry = entry.annotate("*");
+                        }
+                        entries.push(entry);
+                    }
+
+                    let Some(name) = picker::pick(&config.picker.command, &entries)
+                        .context("picking a project")?
+                    else {
+                        return Ok(());
+                    };
+                    let proj = repo
+                        .find_project_by_name(&name, name_match)
+                        .context("getting project")?
+                        .ok_or_else(|| anyhow::anyhow!("no project found for name '{}'", name))?;
+                    ensure_tmux_session(&repo, &config, &proj)?;
+                    let view = repo
+                        .get_active_view_for_project(&proj)
+                        .context("getting active view for project")?;
+                    focus_view(&mut repo, &mut i3, &config, &view)?;
+                }
+
+                PickTarget::View => {
+                    let mut entries = Vec::new();
+                    for view in repo
+                        .list_views(SortOrder::MostRecentlyUsed)
+                        .context("listing views")?
+                    {
+                        let display_name = repo
+                            .get_window_manager_display_name(&view)
+                            .with_context(|| {
+                                format!("getting display name for view '{}'", view.name())
+                            })?;
+                        let mut entry = Entry::new(display_name);
+                        if let Some(key) = repo
+                            .get_pin_key_for_view(&view)
+                            .context("getting pin key for view")?
+                        {
+                            entry = entry.annotate(format!("[{}]", key));
+                        }
+                        if current_view.as_ref() == Some(&view) {
+                            entry = entry.annotate("*");
+                        }
+                        entries.push(entry);
+                    }
+
+                    let Some(display_name) =
+                        picker::pick(&config.picker.command, &entries).context("picking a view")?
+                    else {
+                        return Ok(());
+                    };
+                    let view = repo
+                        .get_view_from_window_manager_display_name(&display_name)?
+                        .ok_or_else(|| {
+                            anyhow::anyhow!("no view found for display name '{}'", display_name)
+                        })?;
+                    focus_view(&mut repo, &mut i3, &config, &view)?;
+                }
+
+                PickTarget::Pin => {
+                    let mut entries = Vec::new();
+                    for pin in repo.list_pins().context("listing pins")? {
+                        // local pins of other projects cannot be focused from here
+                        if !pin.is_in_scope(current_proj.as_ref()) {
+                            continue;
+                        }
+                        let k = pin.key();
+                        if let Some(target) = pin.target() {
+                            entries.push(
+                                Entry::new(k)
+                                    .annotate(pin.pin_type().to_string())
+                                    .annotate(target),
+                            );
+                            continue;
+                        }
+                        let view = repo
+                            .get_view_for_pin(&pin)
+                            .with_context(|| format!("getting view for pin '{}'", k))?
+                            .ok_or_else(|| anyhow::anyhow!("no view found for pin key '{}'", k))?;
+                        let display_name = repo
+                            .get_window_manager_display_name(&view)
+                            .context("getting display name for view")?;
+                        let mut entry = Entry::new(k)
+                            .annotate(pin.pin_type().to_string())
+                            .annotate(display_name);
+                        if current_view.as_ref() == Some(&view) {
+                            entry = entry.annotate("*");
+                        }
+                        entries.push(entry);
+                    }
+
+                    let Some(key) =
+                        picker::pick(&config.picker.command, &entries).context("picking a pin")?
+                    else {
+                        return Ok(());
+                    };
+                    let target = repo
+                        .get_pin_target_for_key(&key, current_view.as_ref())
+                        .context("getting target for pin key")?
+                        .ok_or_else(|| anyhow::anyhow!("nothing found for pin key '{}'", key))?;
+                    focus_pin_target(&mut repo, &mut i3, &config, &target)?;
+                }
+            }
+        }
+
         Commands::View { command } => match command {
             ViewCommands::Rename { new_name } => {
                 let old_display_name = i3
@@ -403,7 +2082,7 @@ fn main() -> anyhow::Result<()> {
                     .ok_or_else(|| {
                         anyhow::anyhow!("no view found for display name '{}'", old_display_name)
                     })?;
-                let updated_view = repo.rename_view(&view, &new_name).with_context(|| {
+                let updated_view = repo.rename_view(&view, new_name).with_context(|| {
                     format!("renaming view '{}' to '{}'", view.name(), new_name)
                 })?;
                 let new_display_name = repo
@@ -421,14 +2100,130 @@ fn main() -> anyhow::Result<()> {
                             old_display_name, new_display_name
                         )
                     })?;
+                let proj = repo
+                    .get_project_from_window_manager_display_name(&new_display_name)?
+                    .ok_or_else(|| {
+                        anyhow::anyhow!("no project found for display name '{}'", new_display_name)
+                    })?;
+                config.hooks.run(&Event::ViewRenamed {
+                    project: proj.name(),
+                    old_view: view.name(),
+                    view: updated_view.name(),
+                    workspace: &new_display_name,
+                });
+            }
+
+            ViewCommands::AddCommand { command } => {
+                let display_name = i3
+                    .get_active_workspace_name()
+                    .context("getting active workspace")?;
+                let view = repo
+                    .get_view_from_window_manager_display_name(&display_name)?
+                    .ok_or_else(|| {
+                        anyhow::anyhow!("no view found for display name '{}'", display_name)
+                    })?;
+                repo.add_view_command(&view, command)
+                    .with_context(|| format!("adding command to view '{}'", display_name))?;
+            }
+
+            ViewCommands::ListCommands {} => {
+                let display_name = i3
+                    .get_active_workspace_name()
+                    .context("getting active workspace")?;
+                let view = repo
+                    .get_view_from_window_manager_display_name(&display_name)?
+                    .ok_or_else(|| {
+                        anyhow::anyhow!("no view found for display name '{}'", display_name)
+                    })?;
+                for command in repo
+                    .list_view_commands(&view)
+                    .with_context(|| format!("listing commands of view '{}'", display_name))?
+                {
+                    println!("{}", command);
+                }
+            }
+
+            ViewCommands::ClearCommands {} => {
+                let display_name = i3
+                    .get_active_workspace_name()
+                    .context("getting active workspace")?;
+                let view = repo
+                    .get_view_from_window_manager_display_name(&display_name)?
+                    .ok_or_else(|| {
+                        anyhow::anyhow!("no view found for display name '{}'", display_name)
+                    })?;
+                repo.clear_view_commands(&view)
+                    .with_context(|| format!("clearing commands of view '{}'", display_name))?;
+            }
+
+            ViewCommands::SaveLayout {} => {
+                let display_name = i3
+                    .get_active_workspace_name()
+                    .context("getting active workspace")?;
+                let view = repo
+                    .get_view_from_window_manager_display_name(&display_name)?
+                    .ok_or_else(|| {
+                        anyhow::anyhow!("no view found for display name '{}'", display_name)
+                    })?;
+                let layout = i3
+                    .get_workspace_layout(&display_name)
+                    .with_context(|| format!("capturing layout of '{}'", display_name))?;
+                repo.set_view_layout(&view, Some(&layout))
+                    .with_context(|| format!("saving layout of view '{}'", display_name))?;
+            }
+
+            ViewCommands::RestoreLayout {} => {
+                let display_name = i3
+                    .get_active_workspace_name()
+                    .context("getting active workspace")?;
+                let view = repo
+                    .get_view_from_window_manager_display_name(&display_name)?
+                    .ok_or_else(|| {
+                        anyhow::anyhow!("no view found for display name '{}'", display_name)
+                    })?;
+                let layout = repo
+                    .get_view_layout(&view)
+                    .with_context(|| format!("getting layout of view '{}'", display_name))?
+                    .ok_or_else(|| anyhow::anyhow!("no layout saved for '{}'", display_name))?;
+                i3.append_layout(&layout)
+                    .with_context(|| format!("restoring layout of '{}'", display_name))?;
+            }
+
+            ViewCommands::ClearLayout {} => {
+                let display_name = i3
+                    .get_active_workspace_name()
+                    .context("getting active workspace")?;
+                let view = repo
+                    .get_view_from_window_manager_display_name(&display_name)?
+                    .ok_or_else(|| {
+                        anyhow::anyhow!("no view found for display name '{}'", display_name)
+                    })?;
+                repo.set_view_layout(&view, None)
+                    .with_context(|| format!("clearing layout of view '{}'", display_name))?;
+            }
+
+            ViewCommands::Windows { view } => {
+                let view = named_or_current_view(&repo, &mut i3, view.as_deref(), name_match)?;
+                for (_, window) in managed_windows(&repo, &mut i3)?
+                    .into_iter()
+                    .filter(|(v, _)| *v == view)
+                {
+                    println!(
+                        "{}\t{}\t{}",
+                        window.con_id(),
+                        window.class().unwrap_or_default(),
+                        window.title().unwrap_or_default()
+                    );
+                }
             }
 
             ViewCommands::List {
                 with_pins,
                 with_unmanaged,
+                sort,
             } => {
-                let view_names = repo
-                    .list_views()
+                let mut view_names = repo
+                    .list_views((*sort).into())
                     .context("listing views")?
                     .iter()
                     .map(|v| {
@@ -438,18 +2233,27 @@ fn main() -> anyhow::Result<()> {
                     })
                     .collect::<Result<Vec<String>, _>>()?;
 
-                let mut unique_names = if *with_unmanaged {
+                if *with_unmanaged {
                     let i3_view_names = i3.get_workspace_names().context("getting workspaces")?;
-                    HashSet::<String>::from_iter(view_names.into_iter().chain(i3_view_names))
+                    if matches!(sort, ListSort::Name) {
+                        view_names = HashSet::<String>::from_iter(
+                            view_names.into_iter().chain(i3_view_names),
+                        )
                         .into_iter()
-                        .collect::<Vec<String>>()
-                } else {
-                    view_names
-                };
-
-                unique_names.sort();
+                        .collect::<Vec<String>>();
+                        view_names.sort();
+                    } else {
+                        let managed = HashSet::<String>::from_iter(view_names.iter().cloned());
+                        let mut unmanaged = i3_view_names
+                            .into_iter()
+                            .filter(|name| !managed.contains(name))
+                            .collect::<Vec<String>>();
+                        unmanaged.sort();
+                        view_names.extend(unmanaged);
+                    }
+                }
 
-                for name in &unique_names {
+                for name in &view_names {
                     let pin_key = if *with_pins {
                         match repo
                             .get_view_from_window_manager_display_name(name)
@@ -0,0 +1,833 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use crate::model::{NameMatch, PinType, Project, Repository, SortOrder, View};
+
+/// The declared projects and pins, read from a TOML file. `muxwm sync`
+/// changes the database to match it.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Manifest {
+    pub projects: Vec<ManifestProject>,
+    /// global pins, from key to `project` or `project/view`
+    pub pins: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ManifestProject {
+    pub name: String,
+    /// the names of the views, in order
+    pub views: Vec<String>,
+    /// the active view of a new project, the first view when not set. The
+    /// active view of an existing project is only changed when this is set.
+    #[serde(default)]
+    pub active: Option<String>,
+    /// the project's root directory, a leading `~/` is the home directory
+    #[serde(default)]
+    pub root: Option<String>,
+    /// local pins, from key to view name
+    #[serde(default)]
+    pub pins: BTreeMap<String, String>,
+}
+
+/// One step of a sync plan. Plans list their changes in the order in which
+/// they are applied.
+#[derive(Debug, PartialEq)]
+pub enum Change {
+    RemovePin {
+        key: String,
+    },
+    RemoveLocalPin {
+        project: String,
+        key: String,
+    },
+    RemoveProject {
+        name: String,
+    },
+    CreateProject {
+        name: String,
+        views: Vec<String>,
+        active: Option<String>,
+    },
+    RenameView {
+        project: String,
+        from: String,
+        to: String,
+    },
+    CreateView {
+        project: String,
+        view: String,
+    },
+    SetActiveView {
+        project: String,
+        view: String,
+    },
+    RemoveView {
+        project: String,
+        view: String,
+    },
+    ReorderViews {
+        project: String,
+        views: Vec<String>,
+    },
+    SetRoot {
+        project: String,
+        root: String,
+    },
+    SetPin {
+        key: String,
+        project: String,
+        view: Option<String>,
+    },
+    SetLocalPin {
+        project: String,
+        key: String,
+        view: String,
+    },
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Change::RemovePin { key } => write!(f, "- remove pin {}", key),
+            Change::RemoveLocalPin { project, key } => {
+                write!(f, "- remove local pin {} of project {}", key, project)
+            }
+            Change::RemoveProject { name } => write!(f, "- remove project {}", name),
+            Change::CreateProject { name, views, .. } => {
+                write!(
+                    f,
+                    "+ create project {} with views {}",
+                    name,
+                    views.join(", ")
+                )
+            }
+            Change::RenameView { project, from, to } => {
+                write!(f, "~ rename view {}/{} to {}", project, from, to)
+            }
+            Change::CreateView { project, view } => {
+                write!(f, "+ create view {}/{}", project, view)
+            }
+            Change::SetActiveView { project, view } => {
+                write!(f, "~ make {}/{} the active view", project, view)
+            }
+            Change::RemoveView { project, view } => {
+                write!(f, "- remove view {}/{}", project, view)
+            }
+            Change::ReorderViews { project, views } => {
+                write!(f, "~ reorder views of {}: {}", project, views.join(", "))
+            }
+            Change::SetRoot { project, root } => {
+                write!(f, "~ set root of {} to {}", project, root)
+            }
+            Change::SetPin { key, project, view } => match view {
+                Some(view) => write!(f, "~ pin {} to view {}/{}", key, project, view),
+                None => write!(f, "~ pin {} to project {}", key, project),
+            },
+            Change::SetLocalPin { project, key, view } => {
+                write!(f, "~ pin {} to view {}/{} locally", key, project, view)
+            }
+        }
+    }
+}
+
+impl Manifest {
+    pub fn read(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("reading manifest {}", path.display()))?;
+        Self::parse(&contents).with_context(|| format!("parsing manifest {}", path.display()))
+    }
+
+    fn parse(contents: &str) -> Result<Self> {
+        let manifest: Self = toml::from_str(contents)?;
+        manifest.validate()?;
+        Ok(manifest)
+    }
+
+    /// check that names are unique and that pins refer to declared views
+    fn validate(&self) -> Result<()> {
+        let mut project_names = HashSet::new();
+        for project in &self.projects {
+            if !project_names.insert(project.name.as_str()) {
+                return Err(anyhow::anyhow!("duplicate project '{}'", project.name));
+            }
+            if project.views.is_empty() {
+                return Err(anyhow::anyhow!("project '{}' has no views", project.name));
+            }
+            let mut view_names = HashSet::new();
+            for view in &project.views {
+                if !view_names.insert(view.as_str()) {
+                    return Err(anyhow::anyhow!(
+                        "duplicate view '{}' in project '{}'",
+                        view,
+                        project.name
+                    ));
+                }
+            }
+            if let Some(active) = &project.active
+                && !view_names.contains(active.as_str())
+            {
+                return Err(anyhow::anyhow!(
+                    "active view '{}' is not a view of project '{}'",
+                    active,
+                    project.name
+                ));
+            }
+            let mut pinned = HashSet::new();
+            for (key, view) in &project.pins {
+                if !pinned.insert(view.as_str()) {
+                    return Err(anyhow::anyhow!(
+                        "view '{}/{}' has more than one local pin",
+                        project.name,
+                        view
+                    ));
+                }
+                if !view_names.contains(view.as_str()) {
+                    return Err(anyhow::anyhow!(
+                        "local pin '{}' refers to unknown view '{}/{}'",
+                        key,
+                        project.name,
+                        view
+                    ));
+                }
+            }
+        }
+
+        let mut pinned = HashSet::new();
+        for (key, target) in &self.pins {
+            if !pinned.insert(target.as_str()) {
+                return Err(anyhow::anyhow!("'{}' has more than one pin", target));
+            }
+            let (project_name, view_name) = parse_pin_target(target);
+            let project = self
+                .projects
+                .iter()
+                .find(|p| p.name == project_name)
+                .ok_or_else(|| {
+                    anyhow::anyhow!("pin '{}' refers to unknown project '{}'", key, project_name)
+                })?;
+            if let Some(view) = view_name
+                && !project.views.iter().any(|v| v == view)
+            {
+                return Err(anyhow::anyhow!(
+                    "pin '{}' refers to unknown view '{}'",
+                    key,
+                    target
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// split a pin target into the project name and, for view pins, the view name
+fn parse_pin_target(target: &str) -> (&str, Option<&str>) {
+    match target.split_once('/') {
+        Some((project, view)) => (project, Some(view)),
+        None => (target, None),
+    }
+}
+
+/// replace a leading `~/` with the home directory
+fn expand_home(path: &str) -> String {
+    match (path.strip_prefix("~/"), std::env::var("HOME")) {
+        (Some(rest), Ok(home)) => format!("{}/{}", home, rest),
+        _ => path.to_string(),
+    }
+}
+
+/// Work out the changes that make the database match the manifest: projects
+/// that are not declared are removed, and within each project, views that
+/// are no longer declared are renamed to the newly declared ones, in order,
+/// before any views are created or removed.
+pub fn plan(repo: &Repository, manifest: &Manifest) -> Result<Vec<Change>> {
+    let mut remove_pins = Vec::new();
+    let mut remove_projects = Vec::new();
+    let mut create_projects = Vec::new();
+    let mut rename_views = Vec::new();
+    let mut create_views = Vec::new();
+    let mut set_active = Vec::new();
+    let mut remove_views = Vec::new();
+    let mut reorder_views = Vec::new();
+    let mut set_roots = Vec::new();
+    let mut set_pins = Vec::new();
+
+    // the names existing views will have once the plan is applied, `None`
+    // for views that are removed, by project and current view name
+    let mut view_names: HashMap<(String, String), Option<String>> = HashMap::new();
+
+    let existing = repo.list_projects(SortOrder::Created)?;
+    for project in &existing {
+        if !manifest.projects.iter().any(|p| p.name == project.name()) {
+            remove_projects.push(Change::RemoveProject {
+                name: project.name().to_string(),
+            });
+        }
+    }
+
+    for declared in &manifest.projects {
+        let root = declared.root.as_deref().map(expand_home);
+        let Some(project) = existing.iter().find(|p| p.name() == declared.name) else {
+            create_projects.push(Change::CreateProject {
+                name: declared.name.clone(),
+                views: declared.views.clone(),
+                active: declared.active.clone(),
+            });
+            if let Some(root) = root {
+                set_roots.push(Change::SetRoot {
+                    project: declared.name.clone(),
+                    root,
+                });
+            }
+            for (key, view) in &declared.pins {
+                set_pins.push(Change::SetLocalPin {
+                    project: declared.name.clone(),
+                    key: key.clone(),
+                    view: view.clone(),
+                });
+            }
+            continue;
+        };
+
+        let views = repo.list_views_for_project(project, SortOrder::Position)?;
+        let stale = views
+            .iter()
+            .filter(|v| !declared.views.iter().any(|d| d == v.name()))
+            .collect::<Vec<_>>();
+        let missing = declared
+            .views
+            .iter()
+            .filter(|d| !views.iter().any(|v| v.name() == d.as_str()))
+            .collect::<Vec<_>>();
+
+        let mut order = Vec::new();
+        for view in &views {
+            let new_name = match stale.iter().position(|v| v.name() == view.name()) {
+                Some(i) if i < missing.len() => {
+                    rename_views.push(Change::RenameView {
+                        project: declared.name.clone(),
+                        from: view.name().to_string(),
+                        to: missing[i].clone(),
+                    });
+                    Some(missing[i].clone())
+                }
+                Some(_) => {
+                    remove_views.push(Change::RemoveView {
+                        project: declared.name.clone(),
+                        view: view.name().to_string(),
+                    });
+                    None
+                }
+                None => Some(view.name().to_string()),
+            };
+            order.extend(new_name.clone());
+            view_names.insert((declared.name.clone(), view.name().to_string()), new_name);
+        }
+        for view in missing.iter().skip(stale.len()) {
+            create_views.push(Change::CreateView {
+                project: declared.name.clone(),
+                view: view.to_string(),
+            });
+            order.push(view.to_string());
+        }
+        if order != declared.views {
+            reorder_views.push(Change::ReorderViews {
+                project: declared.name.clone(),
+                views: declared.views.clone(),
+            });
+        }
+
+        let active = repo.get_active_view_for_project(project)?;
+        let active = view_names[&(declared.name.clone(), active.name().to_string())].clone();
+        let wanted = declared
+            .active
+            .clone()
+            .or_else(|| active.clone())
+            .unwrap_or_else(|| declared.views[0].clone());
+        if active.as_ref() != Some(&wanted) {
+            set_active.push(Change::SetActiveView {
+                project: declared.name.clone(),
+                view: wanted,
+            });
+        }
+
+        if let Some(root) = root
+            && repo.get_project_root(project)?.as_ref() != Some(&root)
+        {
+            set_roots.push(Change::SetRoot {
+                project: declared.name.clone(),
+                root,
+            });
+        }
+
+        let mut local_pins = HashMap::new();
+        for pin in repo.list_pins()? {
+            if pin.pin_type() == PinType::Local && pin.is_in_scope(Some(project)) {
+                let (project_name, view_name) = repo.get_pin_target_names(&pin)?;
+                let view_name = view_name.and_then(|v| view_names[&(project_name, v)].clone());
+                local_pins.insert(pin.key(), view_name);
+            }
+        }
+        for key in local_pins.keys() {
+            if !declared.pins.contains_key(key) {
+                remove_pins.push(Change::RemoveLocalPin {
+                    project: declared.name.clone(),
+                    key: key.clone(),
+                });
+            }
+        }
+        for (key, view) in &declared.pins {
+            if local_pins.get(key) != Some(&Some(view.clone())) {
+                set_pins.push(Change::SetLocalPin {
+                    project: declared.name.clone(),
+                    key: key.clone(),
+                    view: view.clone(),
+                });
+            }
+        }
+    }
+
+    let mut global_pins = HashMap::new();
+    for pin in repo.list_pins()? {
+        // stacks and external pins can not be declared in a manifest, so
+        // they are left alone
+        if matches!(pin.pin_type(), PinType::Local | PinType::Stack) || pin.pin_type().is_external()
+        {
+            continue;
+        }
+        let (project_name, view_name) = repo.get_pin_target_names(&pin)?;
+        let target = match view_name {
+            // a pin on a view that is removed no longer points anywhere
+            Some(view) => view_names
+                .get(&(project_name.clone(), view))
+                .cloned()
+                .flatten()
+                .map(|view| format!("{}/{}", project_name, view)),
+            None => Some(project_name),
+        };
+        global_pins.insert(pin.key(), target);
+    }
+    for key in global_pins.keys() {
+        if !manifest.pins.contains_key(key) {
+            remove_pins.push(Change::RemovePin { key: key.clone() });
+        }
+    }
+    for (key, target) in &manifest.pins {
+        if global_pins.get(key) != Some(&Some(target.clone())) {
+            let (project, view) = parse_pin_target(target);
+            set_pins.push(Change::SetPin {
+                key: key.clone(),
+                project: project.to_string(),
+                view: view.map(str::to_string),
+            });
+        }
+    }
+
+    remove_pins.sort_by_key(|c| c.to_string());
+    Ok([
+        remove_pins,
+        remove_projects,
+        create_projects,
+        rename_views,
+        create_views,
+        set_active,
+        remove_views,
+        reorder_views,
+        set_roots,
+        set_pins,
+    ]
+    .into_iter()
+    .flatten()
+    .collect())
+}
+
+fn project(repo: &Repository, name: &str) -> Result<Project> {
+    repo.get_project_by_name(name)?
+        .ok_or_else(|| anyhow::anyhow!("no project found for name '{}'", name))
+}
+
+fn view(repo: &Repository, project: &Project, name: &str) -> Result<View> {
+    repo.find_view_in_project_by_name(project, name, NameMatch::Exact)?
+        .ok_or_else(|| anyhow::anyhow!("no view named '{}' in project '{}'", name, project.name()))
+}
+
+/// Apply the changes of a plan, in order. They are applied as a whole, as a
+/// single step for `muxwm undo`: when one change fails, none is kept.
+pub fn apply(repo: &mut Repository, changes: &[Change]) -> Result<()> {
+    let description = format!("sync {} change(s) from the manifest", changes.len());
+    repo.batch(description, |repo| {
+        for change in changes {
+            apply_change(repo, change).with_context(|| format!("applying '{}'", change))?;
+        }
+        Ok(())
+    })
+}
+
+fn apply_change(repo: &mut Repository, change: &Change) -> Result<()> {
+    match change {
+        Change::RemovePin { key } => repo.clear_pin(key)?,
+        Change::RemoveLocalPin { project: name, key } => {
+            let proj = project(repo, name)?;
+            repo.clear_local_pin(&proj, key)?;
+        }
+        Change::RemoveProject { name } => {
+            let proj = project(repo, name)?;
+            repo.delete_project(&proj)?;
+        }
+        Change::CreateProject {
+            name,
+            views,
+            active,
+        } => {
+            repo.create_project_with_views(name, views, active.as_deref())?;
+        }
+        Change::RenameView {
+            project: name,
+            from,
+            to,
+        } => {
+            let proj = project(repo, name)?;
+            let view = view(repo, &proj, from)?;
+            repo.rename_view(&view, to)?;
+        }
+        Change::CreateView {
+            project: name,
+            view,
+        } => {
+            let proj = project(repo, name)?;
+            repo.create_view_in_project(&proj, view)?;
+        }
+        Change::SetActiveView {
+            project: name,
+            view: view_name,
+        } => {
+            let proj = project(repo, name)?;
+            let view = view(repo, &proj, view_name)?;
+            repo.set_active_view_for_project(&proj, &view)?;
+        }
+        Change::RemoveView {
+            project: name,
+            view: view_name,
+        } => {
+            let proj = project(repo, name)?;
+            let view = view(repo, &proj, view_name)?;
+            repo.delete_view(&view)?;
+        }
+        Change::ReorderViews {
+            project: name,
+            views,
+        } => {
+            let proj = project(repo, name)?;
+            let views = views
+                .iter()
+                .map(|v| view(repo, &proj, v))
+                .collect::<Result<Vec<_>>>()?;
+            repo.reorder_views(&proj, &views)?;
+        }
+        Change::SetRoot {
+            project: name,
+            root,
+        } => {
+            let proj = project(repo, name)?;
+            repo.set_project_root(&proj, Some(root))?;
+        }
+        Change::SetPin {
+            key,
+            project: name,
+            view: view_name,
+        } => {
+            let proj = project(repo, name)?;
+            match view_name {
+                Some(view_name) => {
+                    let view = view(repo, &proj, view_name)?;
+                    repo.upsert_pin_for_view(key, &view, true)?;
+                }
+                None => {
+                    repo.upsert_pin_for_project(key, &proj, true)?;
+                }
+            }
+        }
+        Change::SetLocalPin {
+            project: name,
+            key,
+            view: view_name,
+        } => {
+            let proj = project(repo, name)?;
+            let view = view(repo, &proj, view_name)?;
+            repo.upsert_local_pin_for_view(key, &view, true)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Change, Manifest, apply, plan};
+    use crate::model::{Repository, SortOrder};
+    use rusqlite::Connection;
+
+    fn view_names(repo: &Repository, project: &str) -> Vec<String> {
+        let proj = repo.get_project_by_name(project).unwrap().unwrap();
+        repo.list_views_for_project(&proj, SortOrder::Position)
+            .unwrap()
+            .iter()
+            .map(|v| v.name().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_when_pins_refer_to_unknown_views() {
+        let manifest = Manifest::parse(
+            r#"
+            [[projects]]
+            name = "proj1"
+            views = ["code"]
+
+            [pins]
+            c = "proj1/docs"
+            "#,
+        );
+        assert!(manifest.is_err());
+
+        let manifest = Manifest::parse(
+            r#"
+            [[projects]]
+            name = "proj1"
+            views = ["code"]
+            pins = { 1 = "term" }
+            "#,
+        );
+        assert!(manifest.is_err());
+
+        let manifest = Manifest::parse(
+            r#"
+            [[projects]]
+            name = "proj1"
+            views = ["code"]
+
+            [pins]
+            a = "proj1/code"
+            b = "proj1/code"
+            "#,
+        );
+        assert!(manifest.is_err());
+    }
+
+    #[test]
+    fn test_plan_when_repository_is_empty() {
+        let conn = Connection::open_in_memory().unwrap();
+        let repo = Repository::new(conn).unwrap();
+        let manifest = Manifest::parse(
+            r#"
+            [[projects]]
+            name = "proj1"
+            views = ["code", "term"]
+            active = "term"
+            root = "/src/proj1"
+            pins = { 1 = "code" }
+
+            [pins]
+            p = "proj1"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            plan(&repo, &manifest).unwrap(),
+            vec![
+                Change::CreateProject {
+                    name: "proj1".to_string(),
+                    views: vec!["code".to_string(), "term".to_string()],
+                    active: Some("term".to_string()),
+                },
+                Change::SetRoot {
+                    project: "proj1".to_string(),
+                    root: "/src/proj1".to_string(),
+                },
+                Change::SetLocalPin {
+                    project: "proj1".to_string(),
+                    key: "1".to_string(),
+                    view: "code".to_string(),
+                },
+                Change::SetPin {
+                    key: "p".to_string(),
+                    project: "proj1".to_string(),
+                    view: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plan_renames_reorders_and_removes() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+        let proj1 = repo
+            .create_project_with_views(
+                "proj1",
+                &["editor".to_string(), "term".to_string(), "old".to_string()],
+                Some("old"),
+            )
+            .unwrap();
+        let term = repo
+            .get_view_at_index_in_project(&proj1, 2)
+            .unwrap()
+            .unwrap();
+        repo.upsert_pin_for_view("t", &term, false).unwrap();
+        repo.upsert_pin_for_project("x", &proj1, false).unwrap();
+        repo.create_project("proj2").unwrap();
+
+        let manifest = Manifest::parse(
+            r#"
+            [[projects]]
+            name = "proj1"
+            views = ["term", "code"]
+
+            [pins]
+            t = "proj1/term"
+            "#,
+        )
+        .unwrap();
+
+        let changes = plan(&repo, &manifest).unwrap();
+        assert_eq!(
+            changes,
+            vec![
+                Change::RemovePin {
+                    key: "x".to_string()
+                },
+                Change::RemoveProject {
+                    name: "proj2".to_string()
+                },
+                Change::RenameView {
+                    project: "proj1".to_string(),
+                    from: "editor".to_string(),
+                    to: "code".to_string(),
+                },
+                Change::SetActiveView {
+                    project: "proj1".to_string(),
+                    view: "term".to_string(),
+                },
+                Change::RemoveView {
+                    project: "proj1".to_string(),
+                    view: "old".to_string(),
+                },
+                Change::ReorderViews {
+                    project: "proj1".to_string(),
+                    views: vec!["term".to_string(), "code".to_string()],
+                },
+            ]
+        );
+
+        apply(&mut repo, &changes).unwrap();
+        assert_eq!(view_names(&repo, "proj1"), vec!["term", "code"]);
+        assert!(repo.get_project_by_name("proj2").unwrap().is_none());
+        assert_eq!(repo.list_pins().unwrap().len(), 1);
+
+        // once applied, there is nothing left to do
+        assert!(plan(&repo, &manifest).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_apply_creates_declared_projects() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+        let manifest = Manifest::parse(
+            r#"
+            [[projects]]
+            name = "proj1"
+            views = ["code", "term"]
+            pins = { 1 = "term" }
+
+            [pins]
+            c = "proj1/code"
+            "#,
+        )
+        .unwrap();
+
+        let changes = plan(&repo, &manifest).unwrap();
+        apply(&mut repo, &changes).unwrap();
+
+        assert_eq!(view_names(&repo, "proj1"), vec!["code", "term"]);
+        let proj1 = repo.get_project_by_name("proj1").unwrap().unwrap();
+        let code = repo.get_active_view_for_project(&proj1).unwrap();
+        let view = repo
+            .get_view_for_pin_key("1", Some(&code))
+            .unwrap()
+            .unwrap();
+        assert_eq!(view.name(), "term");
+        let view = repo.get_view_for_pin_key("c", None).unwrap().unwrap();
+        assert_eq!(view.name(), "code");
+        assert!(plan(&repo, &manifest).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_apply_keeps_nothing_when_a_change_fails() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+        repo.create_project("proj1").unwrap();
+
+        let changes = vec![
+            Change::RemoveProject {
+                name: "proj1".to_string(),
+            },
+            Change::CreateProject {
+                name: "proj2".to_string(),
+                views: vec!["code".to_string()],
+                active: None,
+            },
+            Change::CreateView {
+                project: "proj3".to_string(),
+                view: "code".to_string(),
+            },
+        ];
+        assert!(apply(&mut repo, &changes).is_err());
+
+        let names = repo
+            .list_projects(SortOrder::Created)
+            .unwrap()
+            .iter()
+            .map(|p| p.name().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["proj1"]);
+        assert_eq!(view_names(&repo, "proj1"), vec!["view0"]);
+        assert_eq!(repo.list_journal(10).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_apply_is_undone_in_one_step() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+        repo.create_project("proj1").unwrap();
+        let manifest = Manifest::parse(
+            r#"
+            [[projects]]
+            name = "proj2"
+            views = ["code", "term"]
+
+            [[projects]]
+            name = "proj3"
+            views = ["code"]
+            "#,
+        )
+        .unwrap();
+
+        let changes = plan(&repo, &manifest).unwrap();
+        assert!(changes.len() > 1);
+        apply(&mut repo, &changes).unwrap();
+        assert_eq!(repo.list_journal(10).unwrap().len(), 2);
+
+        repo.undo().unwrap().unwrap();
+        let names = repo
+            .list_projects(SortOrder::Created)
+            .unwrap()
+            .iter()
+            .map(|p| p.name().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["proj1"]);
+    }
+}
@@ -3,7 +3,457 @@ use rusqlite::{
     Connection, OptionalExtension, params,
     types::{FromSql, FromSqlResult, ToSql, ToSqlOutput, ValueRef},
 };
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::state::{
+    ExternalPinState, PinState, ProjectState, STATE_VERSION, StackState, StackViewState, State,
+    ViewState,
+};
+
+/// Schema migrations applied on top of the base schema created in
+/// `Repository::new`. The database's `user_version` records how many of them
+/// have been applied, so new migrations must only ever be appended.
+const MIGRATIONS: &[&str] = &[
+    // 1: record when projects and views were last focused (unix seconds)
+    r#"
+    ALTER TABLE projects ADD COLUMN last_focused_at INTEGER;
+    ALTER TABLE views ADD COLUMN last_focused_at INTEGER;
+    "#,
+    // 2: local pins, whose keys are scoped to a project. The key is no longer
+    // unique across the whole table, so the table has to be rebuilt.
+    r#"
+    CREATE TABLE pins_new (
+        id INTEGER PRIMARY KEY,
+        key TEXT NOT NULL,
+        pin_type TEXT NOT NULL,  -- 'view', 'project' or 'local'
+        view_id INTEGER,         -- NULL for project pins
+        project_id INTEGER,      -- NULL for view pins, the scope of local pins
+
+        FOREIGN KEY(view_id) REFERENCES views(id),
+        FOREIGN KEY(project_id) REFERENCES projects(id),
+        CHECK ((pin_type = 'view' AND view_id IS NOT NULL AND project_id IS NULL) OR
+               (pin_type = 'project' AND project_id IS NOT NULL AND view_id IS NULL) OR
+               (pin_type = 'local' AND view_id IS NOT NULL AND project_id IS NOT NULL))
+    );
+    INSERT INTO pins_new (id, key, pin_type, view_id, project_id)
+        SELECT id, key, pin_type, view_id, project_id FROM pins;
+    DROP TABLE pins;
+    ALTER TABLE pins_new RENAME TO pins;
+
+    CREATE UNIQUE INDEX idx_pins_key ON pins(key) WHERE pin_type != 'local';
+    CREATE UNIQUE INDEX idx_pins_local_key ON pins(project_id, key) WHERE pin_type = 'local';
+    CREATE UNIQUE INDEX idx_pins_view ON pins(view_id) WHERE pin_type = 'view';
+    CREATE UNIQUE INDEX idx_pins_local_view ON pins(view_id) WHERE pin_type = 'local';
+    CREATE UNIQUE INDEX idx_pins_project ON pins(project_id) WHERE pin_type = 'project';
+    "#,
+    // 3: commands launched when an empty view is focused
+    r#"
+    CREATE TABLE view_commands (
+        id INTEGER PRIMARY KEY,
+        view_id INTEGER NOT NULL,
+        position INTEGER NOT NULL,
+        command TEXT NOT NULL,
+
+        FOREIGN KEY(view_id) REFERENCES views(id),
+        UNIQUE(view_id, position)
+    );
+    "#,
+    // 4: the saved i3 layout of a view
+    r#"
+    ALTER TABLE views ADD COLUMN layout TEXT;
+    "#,
+    // 5: the root directory and environment variables of a project
+    r#"
+    ALTER TABLE projects ADD COLUMN root TEXT;
+
+    CREATE TABLE project_env (
+        id INTEGER PRIMARY KEY,
+        project_id INTEGER NOT NULL,
+        name TEXT NOT NULL,
+        value TEXT NOT NULL,
+
+        FOREIGN KEY(project_id) REFERENCES projects(id),
+        UNIQUE(project_id, name)
+    );
+    "#,
+    // 6: the tmux session paired with a project, when not named after it
+    r#"
+    ALTER TABLE projects ADD COLUMN tmux_session TEXT;
+    "#,
+    // 7: intervals during which a view was focused (unix seconds). The
+    // session of the currently focused view has no end yet.
+    r#"
+    CREATE TABLE focus_sessions (
+        id INTEGER PRIMARY KEY,
+        view_id INTEGER NOT NULL,
+        started_at INTEGER NOT NULL,
+        ended_at INTEGER,
+
+        FOREIGN KEY(view_id) REFERENCES views(id)
+    );
+
+    CREATE INDEX idx_focus_sessions_started_at ON focus_sessions(started_at);
+    "#,
+    // 8: the operation journal. Every journaled `Repository` call is one
+    // entry; `journal_changes` holds the rows it changed, as JSON images
+    // from before and after the change (NULL for inserted and deleted rows).
+    r#"
+    CREATE TABLE journal (
+        id INTEGER PRIMARY KEY,
+        at INTEGER NOT NULL,
+        description TEXT NOT NULL,
+        undone_at INTEGER
+    );
+
+    CREATE TABLE journal_changes (
+        id INTEGER PRIMARY KEY,
+        journal_id INTEGER NOT NULL,
+        table_name TEXT NOT NULL,
+        before TEXT,
+        after TEXT,
+
+        FOREIGN KEY(journal_id) REFERENCES journal(id)
+    );
+
+    CREATE INDEX idx_journal_changes_journal_id ON journal_changes(journal_id);
+    "#,
+    // 9: stack pins, whose key holds an ordered list of views kept in
+    // `pin_stack_members`. The pin type check has to change, so the pins
+    // table is rebuilt once more.
+    r#"
+    CREATE TABLE pins_new (
+        id INTEGER PRIMARY KEY,
+        key TEXT NOT NULL,
+        pin_type TEXT NOT NULL,  -- 'view', 'project', 'local' or 'stack'
+        view_id INTEGER,         -- NULL for project and stack pins
+        project_id INTEGER,      -- NULL for view and stack pins, the scope of local pins
+
+        FOREIGN KEY(view_id) REFERENCES views(id),
+        FOREIGN KEY(project_id) REFERENCES projects(id),
+        CHECK ((pin_type = 'view' AND view_id IS NOT NULL AND project_id IS NULL) OR
+               (pin_type = 'project' AND project_id IS NOT NULL AND view_id IS NULL) OR
+               (pin_type = 'local' AND view_id IS NOT NULL AND project_id IS NOT NULL) OR
+               (pin_type = 'stack' AND view_id IS NULL AND project_id IS NULL))
+    );
+    INSERT INTO pins_new (id, key, pin_type, view_id, project_id)
+        SELECT id, key, pin_type, view_id, project_id FROM pins;
+    DROP TABLE pins;
+    ALTER TABLE pins_new RENAME TO pins;
+
+    CREATE UNIQUE INDEX idx_pins_key ON pins(key) WHERE pin_type != 'local';
+    CREATE UNIQUE INDEX idx_pins_local_key ON pins(project_id, key) WHERE pin_type = 'local';
+    CREATE UNIQUE INDEX idx_pins_view ON pins(view_id) WHERE pin_type = 'view';
+    CREATE UNIQUE INDEX idx_pins_local_view ON pins(view_id) WHERE pin_type = 'local';
+    CREATE UNIQUE INDEX idx_pins_project ON pins(project_id) WHERE pin_type = 'project';
+
+    CREATE TABLE pin_stack_members (
+        id INTEGER PRIMARY KEY,
+        pin_id INTEGER NOT NULL,
+        view_id INTEGER NOT NULL,
+        position INTEGER NOT NULL,
+
+        FOREIGN KEY(pin_id) REFERENCES pins(id),
+        FOREIGN KEY(view_id) REFERENCES views(id),
+        UNIQUE(pin_id, view_id),
+        UNIQUE(pin_id, position)
+    );
+    "#,
+    // 10: external pins, which point at what muxwm does not manage: a plain
+    // workspace by name, or a window by con_id or mark, kept in `target`.
+    // The stack members refer to the pins, so they are set aside while the
+    // pins table is rebuilt.
+    r#"
+    CREATE TEMP TABLE pin_stack_members_old AS SELECT * FROM pin_stack_members;
+    DROP TABLE pin_stack_members;
+
+    CREATE TABLE pins_new (
+        id INTEGER PRIMARY KEY,
+        key TEXT NOT NULL,
+        pin_type TEXT NOT NULL,  -- 'view', 'project', 'local', 'stack', 'workspace', 'window' or 'mark'
+        view_id INTEGER,         -- only set for view and local pins
+        project_id INTEGER,      -- only set for project pins, the scope of local pins
+        target TEXT,             -- only set for external pins
+
+        FOREIGN KEY(view_id) REFERENCES views(id),
+        FOREIGN KEY(project_id) REFERENCES projects(id),
+        CHECK ((pin_type = 'view' AND view_id IS NOT NULL AND project_id IS NULL AND target IS NULL) OR
+               (pin_type = 'project' AND project_id IS NOT NULL AND view_id IS NULL AND target IS NULL) OR
+               (pin_type = 'local' AND view_id IS NOT NULL AND project_id IS NOT NULL AND target IS NULL) OR
+               (pin_type = 'stack' AND view_id IS NULL AND project_id IS NULL AND target IS NULL) OR
+               (pin_type IN ('workspace', 'window', 'mark') AND view_id IS NULL AND project_id IS NULL
+                AND target IS NOT NULL))
+    );
+    INSERT INTO pins_new (id, key, pin_type, view_id, project_id)
+        SELECT id, key, pin_type, view_id, project_id FROM pins;
+    DROP TABLE pins;
+    ALTER TABLE pins_new RENAME TO pins;
+
+    CREATE UNIQUE INDEX idx_pins_key ON pins(key) WHERE pin_type != 'local';
+    CREATE UNIQUE INDEX idx_pins_local_key ON pins(project_id, key) WHERE pin_type = 'local';
+    CREATE UNIQUE INDEX idx_pins_view ON pins(view_id) WHERE pin_type = 'view';
+    CREATE UNIQUE INDEX idx_pins_local_view ON pins(view_id) WHERE pin_type = 'local';
+    CREATE UNIQUE INDEX idx_pins_project ON pins(project_id) WHERE pin_type = 'project';
+
+    CREATE TABLE pin_stack_members (
+        id INTEGER PRIMARY KEY,
+        pin_id INTEGER NOT NULL,
+        view_id INTEGER NOT NULL,
+        position INTEGER NOT NULL,
+
+        FOREIGN KEY(pin_id) REFERENCES pins(id),
+        FOREIGN KEY(view_id) REFERENCES views(id),
+        UNIQUE(pin_id, view_id),
+        UNIQUE(pin_id, position)
+    );
+    INSERT INTO pin_stack_members (id, pin_id, view_id, position)
+        SELECT id, pin_id, view_id, position FROM pin_stack_members_old;
+    DROP TABLE pin_stack_members_old;
+    "#,
+    // 11: focus sessions outlive their view. The sessions of a deleted view
+    // keep the names of its project and of the view instead, so the view id
+    // becomes optional and the table is rebuilt.
+    r#"
+    CREATE TABLE focus_sessions_new (
+        id INTEGER PRIMARY KEY,
+        view_id INTEGER,        -- NULL once the view is deleted
+        started_at INTEGER NOT NULL,
+        ended_at INTEGER,
+        project_name TEXT,      -- only set once the view is deleted
+        view_name TEXT,         -- only set once the view is deleted
+
+        FOREIGN KEY(view_id) REFERENCES views(id),
+        CHECK ((view_id IS NOT NULL AND project_name IS NULL AND view_name IS NULL) OR
+               (view_id IS NULL AND project_name IS NOT NULL AND view_name IS NOT NULL))
+    );
+    INSERT INTO focus_sessions_new (id, view_id, started_at, ended_at)
+        SELECT id, view_id, started_at, ended_at FROM focus_sessions;
+    DROP TABLE focus_sessions;
+    ALTER TABLE focus_sessions_new RENAME TO focus_sessions;
+
+    CREATE INDEX idx_focus_sessions_started_at ON focus_sessions(started_at);
+    "#,
+];
+
+/// The tables whose changes are journaled, with the columns that are
+/// recorded and restored by an undo. Columns that only track usage, such as
+/// `last_focused_at`, are left out.
+const JOURNALED_TABLES: &[(&str, &[&str])] = &[
+    (
+        "projects",
+        &["id", "name", "active_view_id", "root", "tmux_session"],
+    ),
+    ("views", &["id", "name", "project_id", "position", "layout"]),
+    (
+        "pins",
+        &["id", "key", "pin_type", "view_id", "project_id", "target"],
+    ),
+    ("view_commands", &["id", "view_id", "position", "command"]),
+    ("project_env", &["id", "project_id", "name", "value"]),
+    (
+        "focus_sessions",
+        &[
+            "id",
+            "view_id",
+            "started_at",
+            "ended_at",
+            "project_name",
+            "view_name",
+        ],
+    ),
+    (
+        "pin_stack_members",
+        &["id", "pin_id", "view_id", "position"],
+    ),
+];
+
+/// Remove the stack pins that no longer hold any view.
+const DELETE_EMPTY_PIN_STACKS: &str = "DELETE FROM pins
+     WHERE pin_type = 'stack' AND id NOT IN (SELECT pin_id FROM pin_stack_members)";
+
+/// Keep the focus sessions of the views selected by `views`, a condition on
+/// the views table, once those views are deleted, by giving them the names of
+/// the view and of its project in place of the view.
+fn detach_focus_sessions_sql(views: &str) -> String {
+    format!(
+        "UPDATE focus_sessions
+         SET project_name = (SELECT projects.name FROM views
+                             JOIN projects ON projects.id = views.project_id
+                             WHERE views.id = focus_sessions.view_id),
+             view_name = (SELECT name FROM views WHERE views.id = focus_sessions.view_id),
+             view_id = NULL
+         WHERE view_id IN (SELECT id FROM views WHERE {})",
+        views
+    )
+}
+
+/// Create the connection's triggers that copy row changes into
+/// `journal_changes` while a journal entry is being recorded, that is while
+/// `journal_recording` holds its id.
+fn create_journal_triggers(conn: &Connection) -> Result<()> {
+    let mut sql = String::from(
+        "CREATE TEMP TABLE IF NOT EXISTS journal_recording (journal_id INTEGER NOT NULL);",
+    );
+    for (table, columns) in JOURNALED_TABLES {
+        let image = |row: &str| {
+            let fields = columns
+                .iter()
+                .map(|c| format!("'{}', {}.{}", c, row, c))
+                .collect::<Vec<_>>();
+            format!("json_object({})", fields.join(", "))
+        };
+        let changed = columns
+            .iter()
+            .map(|c| format!("OLD.{} IS NOT NEW.{}", c, c))
+            .collect::<Vec<_>>()
+            .join(" OR ");
+        sql.push_str(&format!(
+            "
+            CREATE TEMP TRIGGER IF NOT EXISTS journal_{table}_insert AFTER INSERT ON main.{table}
+            WHEN EXISTS (SELECT 1 FROM journal_recording)
+            BEGIN
+                INSERT INTO journal_changes (journal_id, table_name, before, after)
+                SELECT journal_id, '{table}', NULL, {new} FROM journal_recording;
+            END;
+            CREATE TEMP TRIGGER IF NOT EXISTS journal_{table}_update AFTER UPDATE ON main.{table}
+            WHEN EXISTS (SELECT 1 FROM journal_recording) AND ({changed})
+            BEGIN
+                INSERT INTO journal_changes (journal_id, table_name, before, after)
+                SELECT journal_id, '{table}', {old}, {new} FROM journal_recording;
+            END;
+            CREATE TEMP TRIGGER IF NOT EXISTS journal_{table}_delete AFTER DELETE ON main.{table}
+            WHEN EXISTS (SELECT 1 FROM journal_recording)
+            BEGIN
+                INSERT INTO journal_changes (journal_id, table_name, before, after)
+                SELECT journal_id, '{table}', {old}, NULL FROM journal_recording;
+            END;
+            ",
+            table = table,
+            old = image("OLD"),
+            new = image("NEW"),
+            changed = changed,
+        ));
+    }
+    conn.execute_batch(&sql)?;
+    Ok(())
+}
+
+/// Restore the rows changed by a journal entry, in reverse order. Returns the
+/// workspace renames the window manager needs to follow the restored view
+/// names, from the current to the restored display name.
+fn revert_journal_changes(conn: &Connection, journal_id: i64) -> Result<Vec<(String, String)>> {
+    let changes = {
+        let mut stmt = conn.prepare(
+            "SELECT table_name, before, after FROM journal_changes
+             WHERE journal_id = ?1 ORDER BY id DESC",
+        )?;
+        let changes = stmt.query_map(params![journal_id], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, Option<String>>(1)?,
+                row.get::<_, Option<String>>(2)?,
+            ))
+        })?;
+        changes.collect::<Result<Vec<_>, _>>()?
+    };
+
+    let mut renamed_views = Vec::new();
+    for (table, before, after) in &changes {
+        let columns = JOURNALED_TABLES
+            .iter()
+            .find(|(t, _)| t == table)
+            .map(|(_, columns)| *columns)
+            .ok_or_else(|| anyhow::anyhow!("unknown journaled table '{}'", table))?;
+        let values = columns
+            .iter()
+            .map(|c| format!("json_extract(?1, '$.{}')", c))
+            .collect::<Vec<_>>();
+
+        match (before, after) {
+            (None, Some(after)) => {
+                if table == "views" {
+                    // focusing the view since has started sessions
+                    // that are not journaled
+                    conn.execute(
+                        &detach_focus_sessions_sql("id = json_extract(?1, '$.id')"),
+                        params![after],
+                    )?;
+                    // and switching to it is not journaled either
+                    conn.execute(
+                        "UPDATE projects SET active_view_id = COALESCE(
+                             (SELECT id FROM views WHERE project_id = projects.id
+                              AND id != json_extract(?1, '$.id') ORDER BY position LIMIT 1),
+                             active_view_id)
+                         WHERE active_view_id = json_extract(?1, '$.id')",
+                        params![after],
+                    )?;
+                }
+                conn.execute(
+                    &format!("DELETE FROM {} WHERE id = json_extract(?1, '$.id')", table),
+                    params![after],
+                )?;
+            }
+            (Some(before), None) => {
+                conn.execute(
+                    &format!(
+                        "INSERT INTO {} ({}) VALUES ({})",
+                        table,
+                        columns.join(", "),
+                        values.join(", ")
+                    ),
+                    params![before],
+                )?;
+            }
+            (Some(before), Some(after)) => {
+                // only the columns the entry changed are restored, so that
+                // unjournaled changes made since, like switching views, stay
+                let assignments = columns
+                    .iter()
+                    .zip(&values)
+                    .map(|(c, v)| {
+                        format!(
+                            "{c} = CASE WHEN {v} IS json_extract(?2, '$.{c}') THEN {c} ELSE {v} END",
+                            c = c,
+                            v = v
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                conn.execute(
+                    &format!(
+                        "UPDATE {} SET {} WHERE id = json_extract(?1, '$.id')",
+                        table,
+                        assignments.join(", ")
+                    ),
+                    params![before, after],
+                )?;
+                if table == "views" {
+                    renamed_views.push((before, after));
+                }
+            }
+            (None, None) => {}
+        }
+    }
+
+    // the display names are looked up once every row is restored
+    let mut workspace_renames = Vec::new();
+    for (before, after) in renamed_views {
+        let (project_name, old_name, new_name): (String, String, String) = conn.query_row(
+            "SELECT projects.name, json_extract(?2, '$.name'), json_extract(?1, '$.name')
+             FROM projects WHERE projects.id = json_extract(?1, '$.project_id')",
+            params![before, after],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )?;
+        if old_name != new_name {
+            workspace_renames.push((
+                window_manager_display_name(&project_name, &old_name),
+                window_manager_display_name(&project_name, &new_name),
+            ));
+        }
+    }
+    Ok(workspace_renames)
+}
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct View {
@@ -30,12 +480,34 @@ impl Project {
     pub fn name(&self) -> &str {
         &self.name
     }
+
+    pub fn contains_view(&self, view: &View) -> bool {
+        view.project_id == self.id
+    }
 }
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum PinType {
     Project,
     View,
+    /// a view pin whose key only applies within the view's project
+    Local,
+    /// an ordered list of views, focused in turn
+    Stack,
+    /// a workspace that is not a muxwm view, by name
+    Workspace,
+    /// a window, by i3 con_id
+    Window,
+    /// a window, by i3 mark
+    Mark,
+}
+
+impl PinType {
+    /// whether pins of the type point at something muxwm does not manage
+    pub fn is_external(self) -> bool {
+        matches!(self, PinType::Workspace | PinType::Window | PinType::Mark)
+    }
 }
 
 impl FromSql for PinType {
@@ -43,6 +515,11 @@ impl FromSql for PinType {
         match value.as_str()? {
             "project" => Ok(PinType::Project),
             "view" => Ok(PinType::View),
+            "local" => Ok(PinType::Local),
+            "stack" => Ok(PinType::Stack),
+            "workspace" => Ok(PinType::Workspace),
+            "window" => Ok(PinType::Window),
+            "mark" => Ok(PinType::Mark),
             _ => Err(rusqlite::types::FromSqlError::InvalidType),
         }
     }
@@ -53,6 +530,11 @@ impl ToSql for PinType {
         match self {
             PinType::Project => Ok(ToSqlOutput::from("project")),
             PinType::View => Ok(ToSqlOutput::from("view")),
+            PinType::Local => Ok(ToSqlOutput::from("local")),
+            PinType::Stack => Ok(ToSqlOutput::from("stack")),
+            PinType::Workspace => Ok(ToSqlOutput::from("workspace")),
+            PinType::Window => Ok(ToSqlOutput::from("window")),
+            PinType::Mark => Ok(ToSqlOutput::from("mark")),
         }
     }
 }
@@ -62,17 +544,23 @@ impl Display for PinType {
         match self {
             PinType::Project => write!(f, "project"),
             PinType::View => write!(f, "view"),
+            PinType::Local => write!(f, "local"),
+            PinType::Stack => write!(f, "stack"),
+            PinType::Workspace => write!(f, "workspace"),
+            PinType::Window => write!(f, "window"),
+            PinType::Mark => write!(f, "mark"),
         }
     }
 }
 
 #[derive(Debug)]
 pub struct Pin {
-    _id: i64,
+    id: i64,
     key: String,
     pin_type: PinType,
-    _view_id: Option<i64>,
-    _project_id: Option<i64>,
+    view_id: Option<i64>,
+    project_id: Option<i64>,
+    target: Option<String>,
 }
 
 impl Pin {
@@ -83,15 +571,273 @@ impl Pin {
     pub fn pin_type(&self) -> PinType {
         self.pin_type
     }
+
+    /// Whether the pin's key applies when `current_project` is focused.
+    /// Global pins always apply, local pins only within their project.
+    pub fn is_in_scope(&self, current_project: Option<&Project>) -> bool {
+        match self.pin_type {
+            PinType::Local => current_project.is_some_and(|p| Some(p.id) == self.project_id),
+            _ => true,
+        }
+    }
+
+    /// the workspace name, con_id or mark an external pin points at
+    pub fn target(&self) -> Option<&str> {
+        self.target.as_deref()
+    }
+}
+
+/// What a pin key resolves to.
+#[derive(Debug, PartialEq)]
+pub enum PinTarget {
+    View(View),
+    Workspace(String),
+    Window(i64),
+    Mark(String),
+}
+
+/// What setting a pin changed, besides pointing the key at its new target.
+#[derive(Debug, Default, PartialEq)]
+pub struct PinChange {
+    replaced: Option<String>,
+    moved_from: Option<String>,
+}
+
+impl PinChange {
+    /// the previous target of the key, as `project` or `project/view`, when
+    /// the key was repointed
+    pub fn replaced(&self) -> Option<&str> {
+        self.replaced.as_deref()
+    }
+
+    /// the key the target was pinned under before, which has been removed
+    pub fn moved_from(&self) -> Option<&str> {
+        self.moved_from.as_deref()
+    }
+}
+
+/// The order in which listings of projects and views are returned.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum SortOrder {
+    /// most recently focused first, never focused last
+    MostRecentlyUsed,
+    /// alphabetically by name
+    Name,
+    /// in the order in which they were created
+    Created,
+    /// by position within the project. Projects have no position, so they
+    /// fall back to creation order.
+    Position,
+}
+
+/// How focus time is totalled by `Repository::focus_totals`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ReportGrouping {
+    /// one total per project
+    Project,
+    /// one total per view, within its project
+    View,
+}
+
+/// The time spent focused on a project, or on one of its views.
+#[derive(Debug, PartialEq)]
+pub struct FocusTotal {
+    project: String,
+    view: Option<String>,
+    seconds: i64,
+}
+
+impl FocusTotal {
+    pub fn project(&self) -> &str {
+        &self.project
+    }
+
+    /// the view name, when totals are grouped by view
+    pub fn view(&self) -> Option<&str> {
+        self.view.as_deref()
+    }
+
+    pub fn seconds(&self) -> i64 {
+        self.seconds
+    }
+}
+
+#[cfg(test)]
+impl FocusTotal {
+    pub fn new(project: &str, view: Option<&str>, seconds: i64) -> Self {
+        FocusTotal {
+            project: project.to_string(),
+            view: view.map(str::to_string),
+            seconds,
+        }
+    }
+}
+
+/// How [`Repository::import_state`] treats the existing state.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ImportMode {
+    /// add to the existing projects and pins, refusing duplicates
+    Merge,
+    /// remove everything first
+    Replace,
+}
+
+/// How names given on the command line are matched against stored names.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum NameMatch {
+    /// the name must match exactly
+    Exact,
+    /// an exact match wins, otherwise the name may be a prefix, a substring
+    /// or a subsequence of a single stored name (ignoring case)
+    Fuzzy,
+}
+
+/// Resolve `input` against `names`, returning the single name it designates.
+/// Matching is done in tiers of decreasing strictness and the first tier with
+/// any match decides: a unique match is returned, several matches are an
+/// ambiguity error listing the candidates.
+fn resolve_name<'a>(input: &str, names: &[&'a str], mode: NameMatch) -> Result<Option<&'a str>> {
+    if let Some(name) = names.iter().find(|n| **n == input) {
+        return Ok(Some(name));
+    }
+    if mode == NameMatch::Exact {
+        return Ok(None);
+    }
+
+    let needle = input.to_lowercase();
+    let is_subsequence = |haystack: &str| {
+        let mut chars = haystack.chars();
+        needle.chars().all(|c| chars.any(|h| h == c))
+    };
+    let tiers: [&dyn Fn(&str) -> bool; 3] = [
+        &|n| n.starts_with(&needle),
+        &|n| n.contains(&needle),
+        &is_subsequence,
+    ];
+
+    for tier in tiers {
+        let matches = names
+            .iter()
+            .copied()
+            .filter(|n| tier(&n.to_lowercase()))
+            .collect::<Vec<_>>();
+        match matches.as_slice() {
+            [] => continue,
+            [name] => return Ok(Some(name)),
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "'{}' is ambiguous, it matches: {}",
+                    input,
+                    matches.join(", ")
+                ));
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// The name of the window manager workspace that shows the given view.
+pub fn window_manager_display_name(project_name: &str, view_name: &str) -> String {
+    format!("{}#{}", project_name, view_name)
+}
+
+/// Whether `name` has the shape of a workspace managed by muxwm, see
+/// [`window_manager_display_name`]. Such a name may still refer to a project
+/// or view that no longer exists.
+pub fn is_window_manager_display_name(name: &str) -> bool {
+    name.split('#').count() == 2
+}
+
+/// Split a view given by the user as `project/view`, or as a bare `view` of
+/// the current project, into its project and view names. Only the first `/`
+/// separates them, so that view names may contain slashes.
+pub fn parse_view_path(path: &str) -> (Option<&str>, &str) {
+    match path.split_once('/') {
+        Some((project, view)) => (Some(project), view),
+        None => (None, path),
+    }
+}
+
+/// Turn an arbitrary string, such as a directory name, into a valid project
+/// name: `#` separates the project from the view in window manager display
+/// names, so it is replaced, as is whitespace.
+pub fn sanitize_project_name(name: &str) -> String {
+    name.trim()
+        .chars()
+        .map(|c| {
+            if c == '#' || c.is_whitespace() {
+                '-'
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
 }
 
 pub struct Repository {
     conn: Connection,
     default_view_name: String,
+    /// the journal entry being recorded, while a journaled call runs
+    journal_id: Option<i64>,
+}
+
+/// An operation recorded in the journal.
+#[derive(Debug)]
+pub struct JournalEntry {
+    id: i64,
+    at: String,
+    description: String,
+    undone: bool,
+}
+
+impl JournalEntry {
+    pub fn id(&self) -> i64 {
+        self.id
+    }
+
+    /// when the operation ran, as local time
+    pub fn at(&self) -> &str {
+        &self.at
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    pub fn is_undone(&self) -> bool {
+        self.undone
+    }
+}
+
+/// The result of undoing a journaled operation.
+#[derive(Debug)]
+pub struct Undo {
+    description: String,
+    workspace_renames: Vec<(String, String)>,
+}
+
+impl Undo {
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// the window manager workspaces to rename, from the current to the
+    /// restored display name, for views whose name was restored
+    pub fn workspace_renames(&self) -> &[(String, String)] {
+        &self.workspace_renames
+    }
 }
 
 impl Repository {
-    pub fn new(conn: Connection) -> Result<Self> {
+    pub fn new(mut conn: Connection) -> Result<Self> {
         conn.pragma_update(None, "foreign_keys", "ON")?;
         conn.busy_timeout(std::time::Duration::from_secs(2))?;
         conn.execute_batch(
@@ -133,46 +879,297 @@ impl Repository {
             "#,
         )?;
 
+        let version: i64 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+        for (i, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+            let tx = conn.transaction()?;
+            tx.execute_batch(migration)
+                .with_context(|| format!("applying schema migration {}", i + 1))?;
+            tx.pragma_update(None, "user_version", (i + 1) as i64)?;
+            tx.commit()?;
+        }
+
+        create_journal_triggers(&conn).context("creating journal triggers")?;
+
         Ok(Self {
-            conn: conn,
+            conn,
             default_view_name: "view0".to_string(),
+            journal_id: None,
         })
     }
 
-    pub fn create_project(&mut self, name: &str) -> Result<Project> {
-        let tx = self.conn.transaction()?;
+    /// Run a mutating call as one journal entry, so that it can be undone.
+    /// Calls made from within `f` are part of the same entry. The call runs
+    /// within a savepoint, rolled back when `f` fails, so that it changes all
+    /// or nothing, its journal entry included. Entries of calls that changed
+    /// nothing are dropped.
+    fn journaled<T>(
+        &mut self,
+        description: String,
+        f: impl FnOnce(&mut Self) -> Result<T>,
+    ) -> Result<T> {
+        if self.journal_id.is_some() {
+            return f(self);
+        }
 
-        // insert the project
-        tx.execute(
-            "INSERT INTO projects (name, active_view_id) VALUES (?1, ?2)",
-            params![name, 0],
+        self.conn.execute_batch("SAVEPOINT journaled")?;
+        let result = self.record_journal_entry(description, f);
+        self.journal_id = None;
+        match result {
+            Ok(value) => {
+                self.conn.execute_batch("RELEASE journaled")?;
+                Ok(value)
+            }
+            Err(err) => match self
+                .conn
+                .execute_batch("ROLLBACK TO journaled; RELEASE journaled")
+            {
+                Ok(()) => Err(err),
+                Err(rollback) => Err(err.context(format!("rolling back failed: {}", rollback))),
+            },
+        }
+    }
+
+    /// the body of `journaled`, once its savepoint is open
+    fn record_journal_entry<T>(
+        &mut self,
+        description: String,
+        f: impl FnOnce(&mut Self) -> Result<T>,
+    ) -> Result<T> {
+        self.conn.execute(
+            "INSERT INTO journal (at, description) VALUES (?1, ?2)",
+            params![unix_now(), description],
+        )?;
+        let journal_id = self.conn.last_insert_rowid();
+        self.conn.execute(
+            "INSERT INTO journal_recording (journal_id) VALUES (?1)",
+            params![journal_id],
         )?;
-        let project_id: i64 = tx.last_insert_rowid();
+        self.journal_id = Some(journal_id);
 
-        // insert the view
-        tx.execute(
-            "INSERT INTO views (name, project_id, position) VALUES (?1, ?2, ?3)",
-            params![self.default_view_name, project_id, 0],
+        let value = f(self)?;
+
+        self.conn.execute("DELETE FROM journal_recording", [])?;
+        let changes: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM journal_changes WHERE journal_id = ?1",
+            params![journal_id],
+            |row| row.get(0),
         )?;
-        let view_id: i64 = tx.last_insert_rowid();
+        if changes == 0 {
+            self.conn
+                .execute("DELETE FROM journal WHERE id = ?1", params![journal_id])?;
+        }
+        Ok(value)
+    }
 
-        // update the project to point to the new view
-        tx.execute(
-            "UPDATE projects SET active_view_id = ?1 WHERE id = ?2",
-            params![view_id, project_id],
+    /// Run several mutating calls as a single journal entry described by
+    /// `description`. Either all of them are applied or, when one fails, none
+    /// of them is, and a single undo reverts them all.
+    pub fn batch<T>(
+        &mut self,
+        description: String,
+        f: impl FnOnce(&mut Self) -> Result<T>,
+    ) -> Result<T> {
+        self.journaled(description, f)
+    }
+
+    /// describe a view by its display name, for journal entries
+    fn describe_view(&self, view: &View) -> String {
+        self.get_window_manager_display_name(view)
+            .unwrap_or_else(|_| view.name.clone())
+    }
+
+    /// List the most recent journal entries, newest first.
+    pub fn list_journal(&self, limit: usize) -> Result<Vec<JournalEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, datetime(at, 'unixepoch', 'localtime'), description, undone_at IS NOT NULL
+             FROM journal ORDER BY id DESC LIMIT ?1",
         )?;
+        let entries = stmt.query_map(params![limit as i64], |row| {
+            Ok(JournalEntry {
+                id: row.get(0)?,
+                at: row.get(1)?,
+                description: row.get(2)?,
+                undone: row.get(3)?,
+            })
+        })?;
+
+        entries.collect::<Result<Vec<_>, _>>().map_err(Into::into)
+    }
+
+    /// Revert the most recent journal entry that has not been undone yet, by
+    /// restoring the rows it changed in reverse order. Returns `None` when
+    /// there is nothing left to undo.
+    pub fn undo(&mut self) -> Result<Option<Undo>> {
+        let tx = self.conn.transaction()?;
+
+        let Some((journal_id, description)) = tx
+            .query_row(
+                "SELECT id, description FROM journal WHERE undone_at IS NULL
+                 ORDER BY id DESC LIMIT 1",
+                [],
+                |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)),
+            )
+            .optional()?
+        else {
+            return Ok(None);
+        };
+
+        let workspace_renames = revert_journal_changes(&tx, journal_id)?;
 
+        tx.execute(
+            "UPDATE journal SET undone_at = ?1 WHERE id = ?2",
+            params![unix_now(), journal_id],
+        )?;
         tx.commit()?;
 
-        Ok(self
-            .get_project_by_id(project_id)?
-            .expect("INTERNAL ERROR: project not found after creation"))
+        Ok(Some(Undo {
+            description,
+            workspace_renames,
+        }))
     }
 
-    pub fn list_projects(&self) -> Result<Vec<Project>> {
-        let mut stmt = self
-            .conn
-            .prepare("SELECT id, name, active_view_id FROM projects ORDER BY id")?;
+    pub fn create_project(&mut self, name: &str) -> Result<Project> {
+        let view_names = [self.default_view_name.clone()];
+        self.create_project_with_views(name, &view_names, None)
+    }
+
+    /// Create a project along with the given views, in order. The view named
+    /// `active_view_name` (or the first view) becomes the active view.
+    pub fn create_project_with_views(
+        &mut self,
+        name: &str,
+        view_names: &[String],
+        active_view_name: Option<&str>,
+    ) -> Result<Project> {
+        let description = format!("create project '{}'", name);
+        self.journaled(description, |repo| {
+            let active_index = match active_view_name {
+                Some(active) => view_names.iter().position(|v| v == active).ok_or_else(|| {
+                    anyhow::anyhow!("active view '{}' is not in the views", active)
+                })?,
+                None if view_names.is_empty() => {
+                    return Err(anyhow::anyhow!("a project needs at least one view"));
+                }
+                None => 0,
+            };
+
+            let tx = repo.conn.savepoint()?;
+
+            // insert the project
+            tx.execute(
+                "INSERT INTO projects (name, active_view_id) VALUES (?1, ?2)",
+                params![name, 0],
+            )?;
+            let project_id: i64 = tx.last_insert_rowid();
+
+            // insert the views
+            let mut active_view_id = 0;
+            for (position, view_name) in view_names.iter().enumerate() {
+                tx.execute(
+                    "INSERT INTO views (name, project_id, position) VALUES (?1, ?2, ?3)",
+                    params![view_name, project_id, position as i64],
+                )
+                .with_context(|| format!("creating view '{}'", view_name))?;
+                if position == active_index {
+                    active_view_id = tx.last_insert_rowid();
+                }
+            }
+
+            // update the project to point to the active view
+            tx.execute(
+                "UPDATE projects SET active_view_id = ?1 WHERE id = ?2",
+                params![active_view_id, project_id],
+            )?;
+
+            tx.commit()?;
+
+            Ok(repo
+                .get_project_by_id(project_id)?
+                .expect("INTERNAL ERROR: project not found after creation"))
+        })
+    }
+
+    /// Get the project matching `name`, or create it when there is none, with
+    /// the given views (or the default view) as `create_project_with_views`
+    /// does. Returns the project, its active view and whether it was created.
+    pub fn open_project(
+        &mut self,
+        name: &str,
+        name_match: NameMatch,
+        view_names: Option<&[String]>,
+        active_view_name: Option<&str>,
+    ) -> Result<(Project, View, bool)> {
+        let (project, created) = match self.find_project_by_name(name, name_match)? {
+            Some(project) => (project, false),
+            None => {
+                let project = match view_names {
+                    Some(view_names) => {
+                        self.create_project_with_views(name, view_names, active_view_name)?
+                    }
+                    None => self.create_project(name)?,
+                };
+                (project, true)
+            }
+        };
+        let view = self.get_active_view_for_project(&project)?;
+        Ok((project, view, created))
+    }
+
+    /// Delete the project along with its views and everything attached to
+    /// them: pins, stack memberships, launch commands and environment
+    /// variables. Stacks left without views are removed. The focus time of
+    /// the views is kept for reports.
+    pub fn delete_project(&mut self, project: &Project) -> Result<()> {
+        let description = format!("remove project '{}'", project.name);
+        self.journaled(description, |repo| {
+            let tx = repo.conn.savepoint()?;
+
+            tx.execute(
+                "DELETE FROM pin_stack_members WHERE view_id IN (SELECT id FROM views WHERE project_id = ?1)",
+                params![project.id],
+            )?;
+            tx.execute(
+                "DELETE FROM pins
+                 WHERE project_id = ?1 OR view_id IN (SELECT id FROM views WHERE project_id = ?1)",
+                params![project.id],
+            )?;
+            tx.execute(DELETE_EMPTY_PIN_STACKS, [])?;
+            tx.execute(
+                "DELETE FROM view_commands WHERE view_id IN (SELECT id FROM views WHERE project_id = ?1)",
+                params![project.id],
+            )?;
+            tx.execute(
+                &detach_focus_sessions_sql("project_id = ?1"),
+                params![project.id],
+            )?;
+            tx.execute(
+                "DELETE FROM project_env WHERE project_id = ?1",
+                params![project.id],
+            )?;
+            tx.execute(
+                "DELETE FROM views WHERE project_id = ?1",
+                params![project.id],
+            )?;
+            let deleted = tx.execute("DELETE FROM projects WHERE id = ?1", params![project.id])?;
+            if deleted == 0 {
+                return Err(anyhow::anyhow!("project not found"));
+            }
+
+            tx.commit()?;
+            Ok(())
+        })
+    }
+
+    pub fn list_projects(&self, order: SortOrder) -> Result<Vec<Project>> {
+        let order_by = match order {
+            SortOrder::MostRecentlyUsed => "last_focused_at DESC NULLS LAST, id",
+            SortOrder::Name => "name, id",
+            SortOrder::Created | SortOrder::Position => "id",
+        };
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT id, name, active_view_id FROM projects ORDER BY {}",
+            order_by
+        ))?;
         let projects = stmt.query_map([], |row| {
             Ok(Project {
                 id: row.get(0)?,
@@ -218,10 +1215,138 @@ impl Repository {
             .optional()?)
     }
 
-    pub fn list_views_for_project(&self, project: &Project) -> Result<Vec<View>> {
+    /// Set the directory the project's commands run in, or unset it when
+    /// `root` is `None`.
+    pub fn set_project_root(&mut self, project: &Project, root: Option<&str>) -> Result<()> {
+        let description = format!("set root of project '{}'", project.name);
+        self.journaled(description, |repo| {
+            let updated = repo.conn.execute(
+                "UPDATE projects SET root = ?1 WHERE id = ?2",
+                params![root, project.id],
+            )?;
+            if updated == 0 {
+                return Err(anyhow::anyhow!("project not found"));
+            }
+            Ok(())
+        })
+    }
+
+    pub fn get_project_by_root(&self, root: &str) -> Result<Option<Project>> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT id, name, active_view_id FROM projects WHERE root = ?1",
+                params![root],
+                |row| {
+                    Ok(Project {
+                        id: row.get(0)?,
+                        name: row.get(1)?,
+                        active_view_id: row.get(2)?,
+                    })
+                },
+            )
+            .optional()?)
+    }
+
+    pub fn get_project_root(&self, project: &Project) -> Result<Option<String>> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT root FROM projects WHERE id = ?1",
+                params![project.id],
+                |row| row.get(0),
+            )
+            .optional()?
+            .flatten())
+    }
+
+    /// Set the name of the tmux session paired with the project, or go back
+    /// to the default session name when `session` is `None`.
+    pub fn set_project_tmux_session(
+        &mut self,
+        project: &Project,
+        session: Option<&str>,
+    ) -> Result<()> {
+        let description = format!("set tmux session of project '{}'", project.name);
+        self.journaled(description, |repo| {
+            let updated = repo.conn.execute(
+                "UPDATE projects SET tmux_session = ?1 WHERE id = ?2",
+                params![session, project.id],
+            )?;
+            if updated == 0 {
+                return Err(anyhow::anyhow!("project not found"));
+            }
+            Ok(())
+        })
+    }
+
+    pub fn get_project_tmux_session(&self, project: &Project) -> Result<Option<String>> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT tmux_session FROM projects WHERE id = ?1",
+                params![project.id],
+                |row| row.get(0),
+            )
+            .optional()?
+            .flatten())
+    }
+
+    pub fn set_project_env(&mut self, project: &Project, name: &str, value: &str) -> Result<()> {
+        let description = format!("set {} in project '{}'", name, project.name);
+        self.journaled(description, |repo| {
+            repo.conn.execute(
+                "INSERT INTO project_env (project_id, name, value) VALUES (?1, ?2, ?3)
+                 ON CONFLICT(project_id, name) DO UPDATE SET value = ?3",
+                params![project.id, name, value],
+            )?;
+            Ok(())
+        })
+    }
+
+    pub fn unset_project_env(&mut self, project: &Project, name: &str) -> Result<()> {
+        let description = format!("unset {} in project '{}'", name, project.name);
+        self.journaled(description, |repo| {
+            repo.conn.execute(
+                "DELETE FROM project_env WHERE project_id = ?1 AND name = ?2",
+                params![project.id, name],
+            )?;
+            Ok(())
+        })
+    }
+
+    /// List the project's environment variables as `(name, value)` pairs,
+    /// ordered by name.
+    pub fn list_project_env(&self, project: &Project) -> Result<Vec<(String, String)>> {
         let mut stmt = self
             .conn
-            .prepare("SELECT id, name, project_id, position FROM views WHERE project_id = ?1 ORDER BY position ASC")?;
+            .prepare("SELECT name, value FROM project_env WHERE project_id = ?1 ORDER BY name")?;
+        let env = stmt.query_map(params![project.id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+        env.collect::<Result<Vec<_>, _>>().map_err(Into::into)
+    }
+
+    /// Find a project from a name given by the user, see [`NameMatch`].
+    pub fn find_project_by_name(&self, name: &str, mode: NameMatch) -> Result<Option<Project>> {
+        let projects = self.list_projects(SortOrder::Name)?;
+        let names = projects.iter().map(|p| p.name()).collect::<Vec<_>>();
+        let Some(resolved) = resolve_name(name, &names, mode)? else {
+            return Ok(None);
+        };
+        self.get_project_by_name(resolved)
+    }
+
+    pub fn list_views_for_project(&self, project: &Project, order: SortOrder) -> Result<Vec<View>> {
+        let order_by = match order {
+            SortOrder::MostRecentlyUsed => "last_focused_at DESC NULLS LAST, position",
+            SortOrder::Name => "name, position",
+            SortOrder::Created => "id",
+            SortOrder::Position => "position ASC",
+        };
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT id, name, project_id, position FROM views WHERE project_id = ?1 ORDER BY {}",
+            order_by
+        ))?;
         let views = stmt.query_map(params![project.id], |row| {
             Ok(View {
                 id: row.get(0)?,
@@ -258,51 +1383,51 @@ impl Repository {
     }
 
     pub fn create_view_in_project(&mut self, project: &Project, name: &str) -> Result<View> {
-        let tx = self.conn.transaction()?;
-
-        // get the largest position for a view in the project
-        // and increment it by one
-        tx.query_row(
-            "SELECT MAX(position) FROM views WHERE project_id = ?",
-            params![project.id],
-            |row| Ok(row.get::<_, i64>(0)?),
-        )
-        .and_then(|max_position| {
-            tx.execute(
-                "INSERT INTO views (name, project_id, position) VALUES (?1, ?2, ?3)",
-                params![name, project.id, max_position + 1],
+        let description = format!("create view '{}' in project '{}'", name, project.name);
+        self.journaled(description, |repo| {
+            let tx = repo.conn.savepoint()?;
+
+            // get the largest position for a view in the project
+            // and increment it by one
+            tx.query_row(
+                "SELECT MAX(position) FROM views WHERE project_id = ?",
+                params![project.id],
+                |row| row.get::<_, i64>(0),
             )
-        })?;
-        let view_id: i64 = tx.last_insert_rowid();
-
-        tx.commit()?;
-
-        let view = self
-            .get_view_by_id(view_id)
-            .context("getting view by id")?
-            .ok_or_else(|| anyhow::anyhow!("view not found"))?;
-        Ok(view)
+            .and_then(|max_position| {
+                tx.execute(
+                    "INSERT INTO views (name, project_id, position) VALUES (?1, ?2, ?3)",
+                    params![name, project.id, max_position + 1],
+                )
+            })?;
+            let view_id: i64 = tx.last_insert_rowid();
+
+            tx.commit()?;
+
+            let view = repo
+                .get_view_by_id(view_id)
+                .context("getting view by id")?
+                .ok_or_else(|| anyhow::anyhow!("view not found"))?;
+            Ok(view)
+        })
     }
 
-    pub fn set_active_view_for_project(&mut self, project: &Project, view: &View) -> Result<()> {
-        if view.project_id != project.id {
-            return Err(anyhow::anyhow!("view is not in the project"));
+    /// Get the `index`-th view of the project in position order, counting
+    /// from 1.
+    pub fn get_view_at_index_in_project(
+        &self,
+        project: &Project,
+        index: i64,
+    ) -> Result<Option<View>> {
+        if index < 1 {
+            return Ok(None);
         }
 
-        self.conn.execute(
-            "UPDATE projects SET active_view_id = ?1 WHERE id = ?2",
-            params![view.id, project.id],
-        )?;
-
-        Ok(())
-    }
-
-    pub fn get_view_by_id(&self, id: i64) -> Result<Option<View>> {
         Ok(self
             .conn
             .query_row(
-                "SELECT id, name, project_id, position FROM views WHERE id = ?1",
-                params![id],
+                "SELECT id, name, project_id, position FROM views WHERE project_id = ?1 ORDER BY position ASC LIMIT 1 OFFSET ?2",
+                params![project.id, index - 1],
                 |row| {
                     Ok(View {
                         id: row.get(0)?,
@@ -315,25 +1440,239 @@ impl Repository {
             .optional()?)
     }
 
-    pub fn rename_view(&self, view: &View, new_name: &str) -> Result<View> {
-        Ok(self.conn.query_row(
-            "UPDATE views SET name = ? WHERE id = ? RETURNING id, name, project_id, position",
-            params![new_name, view.id],
-            |row| {
-                Ok(View {
-                    id: row.get(0)?,
-                    name: row.get(1)?,
-                    project_id: row.get(2)?,
-                    position: row.get(3)?,
-                })
-            },
-        )?)
+    /// Find a view of the project from a name given by the user, see
+    /// [`NameMatch`].
+    pub fn find_view_in_project_by_name(
+        &self,
+        project: &Project,
+        name: &str,
+        mode: NameMatch,
+    ) -> Result<Option<View>> {
+        let views = self.list_views_for_project(project, SortOrder::Position)?;
+        let names = views.iter().map(|v| v.name()).collect::<Vec<_>>();
+        let Some(resolved) = resolve_name(name, &names, mode)? else {
+            return Ok(None);
+        };
+        Ok(views.iter().find(|v| v.name() == resolved).cloned())
     }
 
-    pub fn list_views(&self) -> Result<Vec<View>> {
-        let mut stmt = self
+    /// Find a view from a path given by the user, see [`parse_view_path`]. A
+    /// bare view name is looked up in `current_project`. Returns `None` when
+    /// the project or the view is not found.
+    pub fn find_view_by_path(
+        &self,
+        path: &str,
+        current_project: Option<&Project>,
+        mode: NameMatch,
+    ) -> Result<Option<View>> {
+        let (project_name, view_name) = parse_view_path(path);
+        let named;
+        let project = match project_name {
+            Some(name) => {
+                named = self.find_project_by_name(name, mode)?;
+                match &named {
+                    Some(project) => project,
+                    None => return Ok(None),
+                }
+            }
+            None => current_project.ok_or_else(|| {
+                anyhow::anyhow!(
+                    "no current project to find view '{}' in, expected PROJECT/VIEW",
+                    view_name
+                )
+            })?,
+        };
+        self.find_view_in_project_by_name(project, view_name, mode)
+    }
+
+    /// Resolve a target named by the user: a pin key, see
+    /// [`Repository::get_pin_target_for_key`], a project, meaning its active
+    /// view, or a view path, see [`Repository::find_view_by_path`], in that
+    /// order of precedence. `current_view` is the focused view, if any, which
+    /// is also the target when nothing is named. Returns `None` when nothing
+    /// is found.
+    pub fn find_target(
+        &self,
+        view: Option<&str>,
+        project: Option<&str>,
+        pin: Option<&str>,
+        current_view: Option<&View>,
+        mode: NameMatch,
+    ) -> Result<Option<PinTarget>> {
+        if let Some(key) = pin {
+            return self.get_pin_target_for_key(key, current_view);
+        }
+        if let Some(name) = project {
+            return match self.find_project_by_name(name, mode)? {
+                Some(project) => Ok(Some(PinTarget::View(
+                    self.get_active_view_for_project(&project)?,
+                ))),
+                None => Ok(None),
+            };
+        }
+        let Some(path) = view else {
+            return Ok(current_view.cloned().map(PinTarget::View));
+        };
+        let current_project = match current_view {
+            Some(current) => self.get_project_by_id(current.project_id)?,
+            None => None,
+        };
+        Ok(self
+            .find_view_by_path(path, current_project.as_ref(), mode)?
+            .map(PinTarget::View))
+    }
+
+    /// Make `view` the active view of `project`. Switching views is
+    /// navigation, so it is not journaled on its own, but it is part of the
+    /// journal entry of a mutating call that makes it.
+    pub fn set_active_view_for_project(&mut self, project: &Project, view: &View) -> Result<()> {
+        if view.project_id != project.id {
+            return Err(anyhow::anyhow!("view is not in the project"));
+        }
+
+        self.conn.execute(
+            "UPDATE projects SET active_view_id = ?1 WHERE id = ?2",
+            params![view.id, project.id],
+        )?;
+
+        Ok(())
+    }
+
+    pub fn get_view_by_id(&self, id: i64) -> Result<Option<View>> {
+        Ok(self
             .conn
-            .prepare("SELECT id, name, project_id, position FROM views ORDER BY id")?;
+            .query_row(
+                "SELECT id, name, project_id, position FROM views WHERE id = ?1",
+                params![id],
+                |row| {
+                    Ok(View {
+                        id: row.get(0)?,
+                        name: row.get(1)?,
+                        project_id: row.get(2)?,
+                        position: row.get(3)?,
+                    })
+                },
+            )
+            .optional()?)
+    }
+
+    pub fn rename_view(&mut self, view: &View, new_name: &str) -> Result<View> {
+        let description = format!(
+            "rename view '{}' to '{}'",
+            self.describe_view(view),
+            new_name
+        );
+        self.journaled(description, |repo| {
+            Ok(repo.conn.query_row(
+                "UPDATE views SET name = ? WHERE id = ? RETURNING id, name, project_id, position",
+                params![new_name, view.id],
+                |row| {
+                    Ok(View {
+                        id: row.get(0)?,
+                        name: row.get(1)?,
+                        project_id: row.get(2)?,
+                        position: row.get(3)?,
+                    })
+                },
+            )?)
+        })
+    }
+
+    /// Delete a view along with its pins, stack memberships and launch
+    /// commands. Its focus time is kept for reports. The project's active view
+    /// can not be deleted.
+    pub fn delete_view(&mut self, view: &View) -> Result<()> {
+        let description = format!("remove view '{}'", self.describe_view(view));
+        self.journaled(description, |repo| {
+            let tx = repo.conn.savepoint()?;
+
+            let is_active: bool = tx
+                .query_row(
+                    "SELECT active_view_id = ?1 FROM projects WHERE id = ?2",
+                    params![view.id, view.project_id],
+                    |row| row.get(0),
+                )
+                .optional()?
+                .ok_or_else(|| anyhow::anyhow!("project not found"))?;
+            if is_active {
+                return Err(anyhow::anyhow!(
+                    "view '{}' is the active view of its project",
+                    view.name
+                ));
+            }
+
+            tx.execute(
+                "DELETE FROM pin_stack_members WHERE view_id = ?1",
+                params![view.id],
+            )?;
+            tx.execute("DELETE FROM pins WHERE view_id = ?1", params![view.id])?;
+            tx.execute(DELETE_EMPTY_PIN_STACKS, [])?;
+            tx.execute(
+                "DELETE FROM view_commands WHERE view_id = ?1",
+                params![view.id],
+            )?;
+            tx.execute(&detach_focus_sessions_sql("id = ?1"), params![view.id])?;
+            let deleted = tx.execute("DELETE FROM views WHERE id = ?1", params![view.id])?;
+            if deleted == 0 {
+                return Err(anyhow::anyhow!("view not found"));
+            }
+
+            tx.commit()?;
+            Ok(())
+        })
+    }
+
+    /// Give the project's views the positions of their order in `views`,
+    /// which must hold every view of the project exactly once.
+    pub fn reorder_views(&mut self, project: &Project, views: &[View]) -> Result<()> {
+        let description = format!("reorder views of project '{}'", project.name);
+        self.journaled(description, |repo| {
+            let tx = repo.conn.savepoint()?;
+
+            let count: i64 = tx.query_row(
+                "SELECT COUNT(*) FROM views WHERE project_id = ?1",
+                params![project.id],
+                |row| row.get(0),
+            )?;
+            let mut ids = views.iter().map(|v| v.id).collect::<Vec<_>>();
+            ids.sort();
+            ids.dedup();
+            if ids.len() as i64 != count || views.iter().any(|v| !project.contains_view(v)) {
+                return Err(anyhow::anyhow!(
+                    "the new order must hold every view of project '{}' once",
+                    project.name
+                ));
+            }
+
+            // move the views out of the way first, positions are unique within
+            // a project
+            tx.execute(
+                "UPDATE views SET position = -1 - position WHERE project_id = ?1",
+                params![project.id],
+            )?;
+            for (position, view) in views.iter().enumerate() {
+                tx.execute(
+                    "UPDATE views SET position = ?1 WHERE id = ?2",
+                    params![position as i64, view.id],
+                )?;
+            }
+
+            tx.commit()?;
+            Ok(())
+        })
+    }
+
+    pub fn list_views(&self, order: SortOrder) -> Result<Vec<View>> {
+        let order_by = match order {
+            SortOrder::MostRecentlyUsed => "views.last_focused_at DESC NULLS LAST, views.id",
+            SortOrder::Name => "projects.name, views.name",
+            SortOrder::Created => "views.id",
+            SortOrder::Position => "projects.id, views.position",
+        };
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT views.id, views.name, views.project_id, views.position FROM views JOIN projects ON projects.id = views.project_id ORDER BY {}",
+            order_by
+        ))?;
         let views = stmt.query_map([], |row| {
             Ok(View {
                 id: row.get(0)?,
@@ -346,6 +1685,180 @@ impl Repository {
         views.collect::<Result<Vec<_>, _>>().map_err(Into::into)
     }
 
+    /// Append a launch command to the view. Launch commands are run when the
+    /// view is focused while its workspace has no windows.
+    pub fn add_view_command(&mut self, view: &View, command: &str) -> Result<()> {
+        let description = format!("add command to view '{}'", self.describe_view(view));
+        self.journaled(description, |repo| {
+            repo.conn.execute(
+                "INSERT INTO view_commands (view_id, position, command)
+                 SELECT ?1, COALESCE(MAX(position) + 1, 0), ?2 FROM view_commands WHERE view_id = ?1",
+                params![view.id, command],
+            )?;
+            Ok(())
+        })
+    }
+
+    /// List the view's launch commands as they were added.
+    pub fn list_view_commands(&self, view: &View) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT command FROM view_commands WHERE view_id = ?1 ORDER BY position ASC",
+        )?;
+        let commands = stmt.query_map(params![view.id], |row| row.get(0))?;
+
+        commands.collect::<Result<Vec<_>, _>>().map_err(Into::into)
+    }
+
+    pub fn clear_view_commands(&mut self, view: &View) -> Result<()> {
+        let description = format!("clear commands of view '{}'", self.describe_view(view));
+        self.journaled(description, |repo| {
+            repo.conn.execute(
+                "DELETE FROM view_commands WHERE view_id = ?1",
+                params![view.id],
+            )?;
+            Ok(())
+        })
+    }
+
+    /// List the view's launch commands ready to be run, with `{project}` and
+    /// `{view}` replaced by the project and view names.
+    pub fn get_launch_commands_for_view(&self, view: &View) -> Result<Vec<String>> {
+        let commands = self.list_view_commands(view)?;
+        if commands.is_empty() {
+            return Ok(commands);
+        }
+
+        let project = self
+            .get_project_by_id(view.project_id)?
+            .ok_or_else(|| anyhow::anyhow!("project not found"))?;
+        Ok(commands
+            .iter()
+            .map(|c| {
+                c.replace("{project}", project.name())
+                    .replace("{view}", view.name())
+            })
+            .collect())
+    }
+
+    /// Store the view's layout, as captured from the window manager, or
+    /// forget it when `layout` is `None`.
+    pub fn set_view_layout(&mut self, view: &View, layout: Option<&str>) -> Result<()> {
+        let description = format!("set layout of view '{}'", self.describe_view(view));
+        self.journaled(description, |repo| {
+            let updated = repo.conn.execute(
+                "UPDATE views SET layout = ?1 WHERE id = ?2",
+                params![layout, view.id],
+            )?;
+            if updated == 0 {
+                return Err(anyhow::anyhow!("view not found"));
+            }
+            Ok(())
+        })
+    }
+
+    pub fn get_view_layout(&self, view: &View) -> Result<Option<String>> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT layout FROM views WHERE id = ?1",
+                params![view.id],
+                |row| row.get(0),
+            )
+            .optional()?
+            .flatten())
+    }
+
+    /// Record that the view (and with it, its project) has just been focused.
+    pub fn mark_view_focused(&mut self, view: &View) -> Result<()> {
+        self.mark_view_focused_at(view, unix_now())
+    }
+
+    fn mark_view_focused_at(&mut self, view: &View, at: i64) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        let updated = tx.execute(
+            "UPDATE views SET last_focused_at = ?1 WHERE id = ?2",
+            params![at, view.id],
+        )?;
+        if updated == 0 {
+            return Err(anyhow::anyhow!("view not found"));
+        }
+        tx.execute(
+            "UPDATE projects SET last_focused_at = ?1 WHERE id = ?2",
+            params![at, view.project_id],
+        )?;
+
+        // refocusing the view that is already focused continues its session
+        let open_view_id: Option<i64> = tx
+            .query_row(
+                "SELECT view_id FROM focus_sessions WHERE ended_at IS NULL",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?
+            .flatten();
+        if open_view_id != Some(view.id) {
+            tx.execute(
+                "UPDATE focus_sessions SET ended_at = MAX(started_at, ?1) WHERE ended_at IS NULL",
+                params![at],
+            )?;
+            tx.execute(
+                "INSERT INTO focus_sessions (view_id, started_at) VALUES (?1, ?2)",
+                params![view.id, at],
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// total the focus time between `since` and `until` (unix seconds),
+    /// largest total first. The open session counts up to `until`.
+    pub fn focus_totals(
+        &self,
+        since: i64,
+        until: i64,
+        grouping: ReportGrouping,
+    ) -> Result<Vec<FocusTotal>> {
+        // the sessions of deleted views are grouped by the names they kept
+        let view_column = match grouping {
+            ReportGrouping::Project => "NULL",
+            ReportGrouping::View => "COALESCE(views.name, focus_sessions.view_name)",
+        };
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT COALESCE(projects.name, focus_sessions.project_name), {}, SUM(
+                 MIN(COALESCE(focus_sessions.ended_at, ?2), ?2)
+                 - MAX(focus_sessions.started_at, ?1)
+             ) AS seconds
+             FROM focus_sessions
+             LEFT JOIN views ON views.id = focus_sessions.view_id
+             LEFT JOIN projects ON projects.id = views.project_id
+             WHERE focus_sessions.started_at < ?2
+               AND COALESCE(focus_sessions.ended_at, ?2) > ?1
+             GROUP BY 1, 2
+             ORDER BY seconds DESC, 1, 2",
+            view_column
+        ))?;
+        let totals = stmt.query_map(params![since, until], |row| {
+            Ok(FocusTotal {
+                project: row.get(0)?,
+                view: row.get(1)?,
+                seconds: row.get(2)?,
+            })
+        })?;
+        totals.collect::<Result<Vec<_>, _>>().map_err(Into::into)
+    }
+
+    /// convert a date (`YYYY-MM-DD`, optionally followed by `HH:MM[:SS]`) in
+    /// local time to unix seconds, using SQLite's date functions
+    pub fn timestamp_for_date(&self, date: &str) -> Result<i64> {
+        let timestamp: Option<i64> = self.conn.query_row(
+            "SELECT CAST(strftime('%s', ?1, 'utc') AS INTEGER)",
+            params![date],
+            |row| row.get(0),
+        )?;
+        timestamp.ok_or_else(|| anyhow::anyhow!("invalid date '{}'", date))
+    }
+
     pub fn get_next_view_for_project(&self, project: &Project) -> Result<View> {
         let active_view = self
             .get_active_view_for_project(project)
@@ -425,8 +1938,8 @@ impl Repository {
             |row| row.get::<_, String>(0),
         );
 
-        if let Some(project_name) = project_name.ok() {
-            Ok(format!("{}#{}", project_name, view.name))
+        if let Ok(project_name) = project_name {
+            Ok(window_manager_display_name(&project_name, &view.name))
         } else {
             Err(anyhow::anyhow!("project not found"))
         }
@@ -462,6 +1975,16 @@ impl Repository {
             .optional()?)
     }
 
+    /// The view shown on the workspace. `None` is returned for workspaces
+    /// that are not managed by muxwm, such as i3's scratchpad, as well as for
+    /// managed workspaces whose view no longer exists.
+    pub fn get_view_for_workspace(&self, workspace: &str) -> Result<Option<View>> {
+        if !is_window_manager_display_name(workspace) {
+            return Ok(None);
+        }
+        self.get_view_from_window_manager_display_name(workspace)
+    }
+
     pub fn get_view_from_window_manager_display_name(&self, name: &str) -> Result<Option<View>> {
         let (project_name, view_name) = self.parse_window_manager_display_name(name)?;
 
@@ -480,37 +2003,298 @@ impl Repository {
             .optional()?)
     }
 
-    pub fn upsert_pin_for_view(&mut self, targetkey: &str, view: &View) -> Result<()> {
-        self.conn.execute(
-            "INSERT INTO pins (key, pin_type, view_id) VALUES (?1, ?2, ?3)
-             ON CONFLICT(key) DO UPDATE
-             SET pin_type = ?2, project_id = NULL, view_id = ?3",
-            params![targetkey, PinType::View, view.id],
-        )?;
-        Ok(())
+    /// Pin the view under the key. A key that points elsewhere is only
+    /// repointed with `force`, and a view that already has a key loses it,
+    /// so the pin moves to the new key. Returns `None` when the key already
+    /// points to the view.
+    pub fn upsert_pin_for_view(
+        &mut self,
+        targetkey: &str,
+        view: &View,
+        force: bool,
+    ) -> Result<Option<PinChange>> {
+        let description = format!("pin '{}' to view '{}'", targetkey, self.describe_view(view));
+        self.journaled(description, |repo| {
+            let existing = repo.get_pin_by_key(targetkey)?;
+            if existing
+                .as_ref()
+                .is_some_and(|p| p.pin_type == PinType::View && p.view_id == Some(view.id))
+            {
+                return Ok(None);
+            }
+            let replaced = repo.check_pin_overwrite(targetkey, existing.as_ref(), force)?;
+
+            let moved_from = repo.get_pin_key_for_view(view)?;
+            repo.conn.execute(
+                "DELETE FROM pins WHERE pin_type = 'view' AND view_id = ?1",
+                params![view.id],
+            )?;
+            repo.delete_pin_stack_members(targetkey)?;
+            repo.conn.execute(
+                "INSERT INTO pins (key, pin_type, view_id) VALUES (?1, ?2, ?3)
+                 ON CONFLICT(key) WHERE pin_type != 'local' DO UPDATE
+                 SET pin_type = ?2, project_id = NULL, view_id = ?3, target = NULL",
+                params![targetkey, PinType::View, view.id],
+            )?;
+
+            Ok(Some(PinChange {
+                replaced,
+                moved_from,
+            }))
+        })
     }
 
-    pub fn upsert_pin_for_project(&mut self, targetkey: &str, project: &Project) -> Result<()> {
-        self.conn.execute(
-            "INSERT INTO pins (key, pin_type, project_id) VALUES (?1, ?2, ?3)
-             ON CONFLICT(key) DO UPDATE
-             SET pin_type = ?2, view_id = NULL, project_id = ?3",
-            params![targetkey, PinType::Project, project.id],
-        )?;
-        Ok(())
+    /// Pin the project under the key, with the same rules as
+    /// [`Repository::upsert_pin_for_view`].
+    pub fn upsert_pin_for_project(
+        &mut self,
+        targetkey: &str,
+        project: &Project,
+        force: bool,
+    ) -> Result<Option<PinChange>> {
+        let description = format!("pin '{}' to project '{}'", targetkey, project.name);
+        self.journaled(description, |repo| {
+            let existing = repo.get_pin_by_key(targetkey)?;
+            if existing
+                .as_ref()
+                .is_some_and(|p| p.pin_type == PinType::Project && p.project_id == Some(project.id))
+            {
+                return Ok(None);
+            }
+            let replaced = repo.check_pin_overwrite(targetkey, existing.as_ref(), force)?;
+
+            let moved_from = repo.get_pin_key_for_project(project)?;
+            repo.conn.execute(
+                "DELETE FROM pins WHERE pin_type = 'project' AND project_id = ?1",
+                params![project.id],
+            )?;
+            repo.delete_pin_stack_members(targetkey)?;
+            repo.conn.execute(
+                "INSERT INTO pins (key, pin_type, project_id) VALUES (?1, ?2, ?3)
+                 ON CONFLICT(key) WHERE pin_type != 'local' DO UPDATE
+                 SET pin_type = ?2, view_id = NULL, project_id = ?3, target = NULL",
+                params![targetkey, PinType::Project, project.id],
+            )?;
+
+            Ok(Some(PinChange {
+                replaced,
+                moved_from,
+            }))
+        })
     }
 
-    pub fn list_pins(&self) -> Result<Vec<Pin>> {
-        let mut stmt = self
+    /// Pin the view under a key that is scoped to the view's project, so the
+    /// same key can point at a different view in every project. Follows the
+    /// rules of [`Repository::upsert_pin_for_view`] within the project.
+    pub fn upsert_local_pin_for_view(
+        &mut self,
+        targetkey: &str,
+        view: &View,
+        force: bool,
+    ) -> Result<Option<PinChange>> {
+        let description = format!(
+            "pin '{}' to view '{}' locally",
+            targetkey,
+            self.describe_view(view)
+        );
+        self.journaled(description, |repo| {
+            let existing = repo.get_local_pin_by_key(view.project_id, targetkey)?;
+            if existing
+                .as_ref()
+                .is_some_and(|p| p.view_id == Some(view.id))
+            {
+                return Ok(None);
+            }
+            let replaced = repo.check_pin_overwrite(targetkey, existing.as_ref(), force)?;
+
+            let moved_from = repo.get_local_pin_key_for_view(view)?;
+            repo.conn.execute(
+                "DELETE FROM pins WHERE pin_type = 'local' AND view_id = ?1",
+                params![view.id],
+            )?;
+            repo.conn.execute(
+                "INSERT INTO pins (key, pin_type, view_id, project_id) VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT(project_id, key) WHERE pin_type = 'local' DO UPDATE
+                 SET view_id = ?3",
+                params![targetkey, PinType::Local, view.id, view.project_id],
+            )?;
+
+            Ok(Some(PinChange {
+                replaced,
+                moved_from,
+            }))
+        })
+    }
+
+    /// Pin a workspace, window or mark that muxwm does not manage under the
+    /// key, with the same rules as [`Repository::upsert_pin_for_view`].
+    pub fn upsert_external_pin(
+        &mut self,
+        targetkey: &str,
+        pin_type: PinType,
+        target: &str,
+        force: bool,
+    ) -> Result<Option<PinChange>> {
+        if !pin_type.is_external() {
+            return Err(anyhow::anyhow!("{} pins are not external", pin_type));
+        }
+        if pin_type == PinType::Window && target.parse::<i64>().is_err() {
+            return Err(anyhow::anyhow!("invalid con_id '{}'", target));
+        }
+        if pin_type == PinType::Workspace && is_window_manager_display_name(target) {
+            return Err(anyhow::anyhow!(
+                "workspace '{}' is managed by muxwm, pin its view instead",
+                target
+            ));
+        }
+        let description = format!("pin '{}' to {} '{}'", targetkey, pin_type, target);
+        self.journaled(description, |repo| {
+            let existing = repo.get_pin_by_key(targetkey)?;
+            if existing
+                .as_ref()
+                .is_some_and(|p| p.pin_type == pin_type && p.target() == Some(target))
+            {
+                return Ok(None);
+            }
+            let replaced = repo.check_pin_overwrite(targetkey, existing.as_ref(), force)?;
+
+            let moved_from: Option<String> = repo
+                .conn
+                .query_row(
+                    "SELECT key FROM pins WHERE pin_type = ?1 AND target = ?2",
+                    params![pin_type, target],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            repo.conn.execute(
+                "DELETE FROM pins WHERE pin_type = ?1 AND target = ?2",
+                params![pin_type, target],
+            )?;
+            repo.delete_pin_stack_members(targetkey)?;
+            repo.conn.execute(
+                "INSERT INTO pins (key, pin_type, target) VALUES (?1, ?2, ?3)
+                 ON CONFLICT(key) WHERE pin_type != 'local' DO UPDATE
+                 SET pin_type = ?2, view_id = NULL, project_id = NULL, target = ?3",
+                params![targetkey, pin_type, target],
+            )?;
+
+            Ok(Some(PinChange {
+                replaced,
+                moved_from,
+            }))
+        })
+    }
+
+    /// Describe what `existing`, the pin holding `key`, points to, failing
+    /// unless `force` allows repointing it.
+    fn check_pin_overwrite(
+        &self,
+        key: &str,
+        existing: Option<&Pin>,
+        force: bool,
+    ) -> Result<Option<String>> {
+        let Some(pin) = existing else {
+            return Ok(None);
+        };
+        let target = self.describe_pin_target(pin)?;
+        if !force {
+            return Err(anyhow::anyhow!(
+                "pin '{}' already points to {}",
+                key,
+                target
+            ));
+        }
+        Ok(Some(target))
+    }
+
+    /// describe a pin's target as `project` or `project/view`, a stack by its
+    /// number of views, and an external pin by its type and target
+    fn describe_pin_target(&self, pin: &Pin) -> Result<String> {
+        if pin.pin_type.is_external() {
+            return Ok(format!(
+                "{} '{}'",
+                pin.pin_type,
+                pin.target().unwrap_or_default()
+            ));
+        }
+        if pin.pin_type == PinType::Stack {
+            return Ok(format!(
+                "a stack of {} view(s)",
+                self.list_pin_stack_views(pin)?.len()
+            ));
+        }
+        Ok(match self.get_pin_target_names(pin)? {
+            (project, Some(view)) => format!("{}/{}", project, view),
+            (project, None) => project,
+        })
+    }
+
+    /// Get the global pin under the key, if any.
+    pub fn get_pin_by_key(&self, key: &str) -> Result<Option<Pin>> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT id, key, pin_type, view_id, project_id, target FROM pins
+                 WHERE key = ?1 AND pin_type != 'local'",
+                params![key],
+                |row| {
+                    Ok(Pin {
+                        id: row.get(0)?,
+                        key: row.get(1)?,
+                        pin_type: row.get(2)?,
+                        view_id: row.get(3)?,
+                        project_id: row.get(4)?,
+                        target: row.get(5)?,
+                    })
+                },
+            )
+            .optional()?)
+    }
+
+    fn get_local_pin_by_key(&self, project_id: i64, key: &str) -> Result<Option<Pin>> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT id, key, pin_type, view_id, project_id, target FROM pins
+                 WHERE key = ?1 AND pin_type = 'local' AND project_id = ?2",
+                params![key, project_id],
+                |row| {
+                    Ok(Pin {
+                        id: row.get(0)?,
+                        key: row.get(1)?,
+                        pin_type: row.get(2)?,
+                        view_id: row.get(3)?,
+                        project_id: row.get(4)?,
+                        target: row.get(5)?,
+                    })
+                },
+            )
+            .optional()?)
+    }
+
+    fn get_local_pin_key_for_view(&self, view: &View) -> Result<Option<String>> {
+        Ok(self
             .conn
-            .prepare("SELECT id, key, pin_type, view_id, project_id FROM pins ORDER BY id")?;
+            .query_row(
+                "SELECT key FROM pins WHERE pin_type = 'local' AND view_id = ?1",
+                params![view.id],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    pub fn list_pins(&self) -> Result<Vec<Pin>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, key, pin_type, view_id, project_id, target FROM pins ORDER BY id",
+        )?;
         let pins = stmt.query_map([], |row| {
             Ok(Pin {
-                _id: row.get(0)?,
+                id: row.get(0)?,
                 key: row.get(1)?,
                 pin_type: row.get(2)?,
-                _view_id: row.get(3)?,
-                _project_id: row.get(4)?,
+                view_id: row.get(3)?,
+                project_id: row.get(4)?,
+                target: row.get(5)?,
             })
         })?;
 
@@ -518,13 +2302,253 @@ impl Repository {
     }
 
     pub fn clear_pin(&mut self, key: &str) -> Result<()> {
-        self.conn
-            .execute("DELETE FROM pins WHERE key = ?1", params![key])
-            .optional()?;
+        let description = format!("clear pin '{}'", key);
+        self.journaled(description, |repo| {
+            repo.delete_pin_stack_members(key)?;
+            repo.conn
+                .execute(
+                    "DELETE FROM pins WHERE key = ?1 AND pin_type != 'local'",
+                    params![key],
+                )
+                .optional()?;
+            Ok(())
+        })
+    }
+
+    pub fn clear_local_pin(&mut self, project: &Project, key: &str) -> Result<()> {
+        let description = format!("clear local pin '{}' of project '{}'", key, project.name);
+        self.journaled(description, |repo| {
+            repo.conn.execute(
+                "DELETE FROM pins WHERE key = ?1 AND pin_type = 'local' AND project_id = ?2",
+                params![key, project.id],
+            )?;
+            Ok(())
+        })
+    }
+
+    /// Give the global pin `old_key` the key `new_key`, which must be unused.
+    pub fn rename_pin(&mut self, old_key: &str, new_key: &str) -> Result<()> {
+        let description = format!("rename pin '{}' to '{}'", old_key, new_key);
+        self.journaled(description, |repo| {
+            if repo.get_pin_by_key(new_key)?.is_some() {
+                return Err(anyhow::anyhow!("pin '{}' already exists", new_key));
+            }
+            let updated = repo.conn.execute(
+                "UPDATE pins SET key = ?2 WHERE key = ?1 AND pin_type != 'local'",
+                params![old_key, new_key],
+            )?;
+            if updated == 0 {
+                return Err(anyhow::anyhow!("pin '{}' not found", old_key));
+            }
+            Ok(())
+        })
+    }
+
+    /// Exchange the targets of two global pins. The keys are swapped through
+    /// a placeholder, since keys are unique at every step.
+    pub fn swap_pins(&mut self, key_a: &str, key_b: &str) -> Result<()> {
+        let description = format!("swap pins '{}' and '{}'", key_a, key_b);
+        self.journaled(description, |repo| {
+            for key in [key_a, key_b] {
+                if repo.get_pin_by_key(key)?.is_none() {
+                    return Err(anyhow::anyhow!("pin '{}' not found", key));
+                }
+            }
+            if key_a == key_b {
+                return Ok(());
+            }
+
+            let tx = repo.conn.savepoint()?;
+            let placeholder = "\0swap";
+            for (from, to) in [(key_a, placeholder), (key_b, key_a), (placeholder, key_b)] {
+                tx.execute(
+                    "UPDATE pins SET key = ?2 WHERE key = ?1 AND pin_type != 'local'",
+                    params![from, to],
+                )?;
+            }
+            tx.commit()?;
+            Ok(())
+        })
+    }
+
+    /// Append the view to the stack under the key, creating the stack when
+    /// the key is unused. A key held by another kind of pin is refused.
+    pub fn add_view_to_pin_stack(&mut self, key: &str, view: &View) -> Result<()> {
+        let description = format!(
+            "add view '{}' to pin stack '{}'",
+            self.describe_view(view),
+            key
+        );
+        self.journaled(description, |repo| {
+            let pin_id = match repo.get_pin_by_key(key)? {
+                Some(pin) if pin.pin_type == PinType::Stack => pin.id,
+                Some(pin) => {
+                    return Err(anyhow::anyhow!(
+                        "pin '{}' is a {} pin, not a stack",
+                        key,
+                        pin.pin_type
+                    ));
+                }
+                None => {
+                    repo.conn.execute(
+                        "INSERT INTO pins (key, pin_type) VALUES (?1, ?2)",
+                        params![key, PinType::Stack],
+                    )?;
+                    repo.conn.last_insert_rowid()
+                }
+            };
+
+            let is_member: bool = repo.conn.query_row(
+                "SELECT EXISTS (SELECT 1 FROM pin_stack_members WHERE pin_id = ?1 AND view_id = ?2)",
+                params![pin_id, view.id],
+                |row| row.get(0),
+            )?;
+            if is_member {
+                return Err(anyhow::anyhow!(
+                    "view '{}' is already in pin stack '{}'",
+                    view.name,
+                    key
+                ));
+            }
+            repo.conn.execute(
+                "INSERT INTO pin_stack_members (pin_id, view_id, position)
+                 SELECT ?1, ?2, COALESCE(MAX(position), -1) + 1
+                 FROM pin_stack_members WHERE pin_id = ?1",
+                params![pin_id, view.id],
+            )?;
+            Ok(())
+        })
+    }
+
+    /// Take the view out of the stack under the key. The stack is removed
+    /// along with its last view.
+    pub fn remove_view_from_pin_stack(&mut self, key: &str, view: &View) -> Result<()> {
+        let description = format!(
+            "remove view '{}' from pin stack '{}'",
+            self.describe_view(view),
+            key
+        );
+        self.journaled(description, |repo| {
+            let pin = repo
+                .get_pin_by_key(key)?
+                .filter(|p| p.pin_type == PinType::Stack)
+                .ok_or_else(|| anyhow::anyhow!("pin stack '{}' not found", key))?;
+
+            let tx = repo.conn.savepoint()?;
+            let deleted = tx.execute(
+                "DELETE FROM pin_stack_members WHERE pin_id = ?1 AND view_id = ?2",
+                params![pin.id, view.id],
+            )?;
+            if deleted == 0 {
+                return Err(anyhow::anyhow!(
+                    "view '{}' is not in pin stack '{}'",
+                    view.name,
+                    key
+                ));
+            }
+            tx.execute(DELETE_EMPTY_PIN_STACKS, [])?;
+            tx.commit()?;
+            Ok(())
+        })
+    }
+
+    /// List the views of a stack pin in order. Other pins have none.
+    pub fn list_pin_stack_views(&self, pin: &Pin) -> Result<Vec<View>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT views.id, views.name, views.project_id, views.position
+             FROM pin_stack_members JOIN views ON pin_stack_members.view_id = views.id
+             WHERE pin_stack_members.pin_id = ?1
+             ORDER BY pin_stack_members.position",
+        )?;
+        let views = stmt.query_map(params![pin.id], |row| {
+            Ok(View {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                project_id: row.get(2)?,
+                position: row.get(3)?,
+            })
+        })?;
+
+        views.collect::<Result<Vec<_>, _>>().map_err(Into::into)
+    }
+
+    /// remove the views of the stack pin under the key, if it is one
+    fn delete_pin_stack_members(&self, key: &str) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM pin_stack_members
+             WHERE pin_id IN (SELECT id FROM pins WHERE key = ?1 AND pin_type = 'stack')",
+            params![key],
+        )?;
         Ok(())
     }
 
-    pub fn get_view_for_pin_key(&self, key: &str) -> Result<Option<View>> {
+    /// Get the view a pin currently points to: the pinned view for view and
+    /// local pins, the active view of the pinned project for project pins,
+    /// and the first view of a stack. External pins point at no view.
+    pub fn get_view_for_pin(&self, pin: &Pin) -> Result<Option<View>> {
+        match pin.pin_type {
+            PinType::Workspace | PinType::Window | PinType::Mark => Ok(None),
+            PinType::Stack => Ok(self.list_pin_stack_views(pin)?.into_iter().next()),
+            PinType::View | PinType::Local => self.get_view_by_id(
+                pin.view_id
+                    .ok_or_else(|| anyhow::anyhow!("invalid state: view pin without a view"))?,
+            ),
+            PinType::Project => {
+                let project_id = pin.project_id.ok_or_else(|| {
+                    anyhow::anyhow!("invalid state: project pin without a project")
+                })?;
+                match self.get_project_by_id(project_id)? {
+                    Some(project) => Ok(Some(self.get_active_view_for_project(&project)?)),
+                    None => Ok(None),
+                }
+            }
+        }
+    }
+
+    /// Resolve a pin key to a view. `current_view` is the focused view, if
+    /// any: a local pin of its project takes precedence over a global pin
+    /// with the same key, and a stack resolves to the view after it, wrapping
+    /// around, or to its first view when the focused view is not in the
+    /// stack.
+    pub fn get_view_for_pin_key(
+        &self,
+        key: &str,
+        current_view: Option<&View>,
+    ) -> Result<Option<View>> {
+        if let Some(current) = current_view {
+            let local = self
+                .conn
+                .query_row(
+                    "SELECT views.id, views.name, views.project_id, views.position
+                     FROM pins JOIN views ON pins.view_id = views.id
+                     WHERE pins.pin_type = 'local' AND pins.project_id = ?1 AND pins.key = ?2",
+                    params![current.project_id, key],
+                    |row| {
+                        Ok(View {
+                            id: row.get(0)?,
+                            name: row.get(1)?,
+                            project_id: row.get(2)?,
+                            position: row.get(3)?,
+                        })
+                    },
+                )
+                .optional()?;
+            if local.is_some() {
+                return Ok(local);
+            }
+        }
+
+        if let Some(pin) = self
+            .get_pin_by_key(key)?
+            .filter(|p| p.pin_type == PinType::Stack)
+        {
+            let views = self.list_pin_stack_views(&pin)?;
+            let next = current_view
+                .and_then(|current| views.iter().position(|v| v == current))
+                .map_or(0, |i| (i + 1) % views.len());
+            return Ok(views.into_iter().nth(next));
+        }
+
         Ok(self
             .conn
             .query_row(
@@ -549,7 +2573,7 @@ impl Repository {
               LEFT JOIN views v1 ON pins.view_id = v1.id
               LEFT JOIN projects p ON pins.project_id = p.id
               LEFT JOIN views v2 ON p.active_view_id = v2.id
-              WHERE pins.key = ?",
+              WHERE pins.key = ? AND pins.pin_type IN ('view', 'project')",
                 params![key],
                 |row| {
                     Ok(View {
@@ -563,13 +2587,52 @@ impl Repository {
             .optional()?)
     }
 
-    pub fn get_pin_key_for_view(&self, view: &View) -> Result<Option<String>> {
-        Ok(self
-            .conn
-            .query_row(
-                "SELECT key FROM pins WHERE pin_type = 'view' AND view_id = ?1",
+    /// Resolve a pin key to what it points to, following the rules of
+    /// [`Repository::get_view_for_pin_key`] for pins of views and projects.
+    pub fn get_pin_target_for_key(
+        &self,
+        key: &str,
+        current_view: Option<&View>,
+    ) -> Result<Option<PinTarget>> {
+        let external = match current_view {
+            Some(current)
+                if self
+                    .get_local_pin_by_key(current.project_id, key)?
+                    .is_some() =>
+            {
+                None
+            }
+            _ => self
+                .get_pin_by_key(key)?
+                .filter(|p| p.pin_type.is_external()),
+        };
+        let Some(pin) = external else {
+            return Ok(self
+                .get_view_for_pin_key(key, current_view)?
+                .map(PinTarget::View));
+        };
+
+        let target = pin
+            .target
+            .ok_or_else(|| anyhow::anyhow!("invalid state: external pin without a target"))?;
+        Ok(Some(match pin.pin_type {
+            PinType::Workspace => PinTarget::Workspace(target),
+            PinType::Window => PinTarget::Window(
+                target
+                    .parse()
+                    .with_context(|| format!("invalid con_id '{}'", target))?,
+            ),
+            _ => PinTarget::Mark(target),
+        }))
+    }
+
+    pub fn get_pin_key_for_view(&self, view: &View) -> Result<Option<String>> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT key FROM pins WHERE pin_type = 'view' AND view_id = ?1",
                 params![view.id],
-                |row| Ok(row.get(0)?),
+                |row| row.get(0),
             )
             .optional()?)
     }
@@ -580,17 +2643,412 @@ impl Repository {
             .query_row(
                 "SELECT key FROM pins WHERE pin_type = 'project' AND project_id = ?1",
                 params![project.id],
-                |row| Ok(row.get(0)?),
+                |row| row.get(0),
             )
             .optional()?)
     }
+
+    /// Get the names of what the pin points to: the project, and the view
+    /// for view and local pins. Stacks have no single target.
+    pub fn get_pin_target_names(&self, pin: &Pin) -> Result<(String, Option<String>)> {
+        match pin.pin_type {
+            PinType::Stack => Err(anyhow::anyhow!(
+                "pin '{}' is a stack of several views",
+                pin.key
+            )),
+            PinType::Workspace | PinType::Window | PinType::Mark => {
+                Err(anyhow::anyhow!("pin '{}' points outside of muxwm", pin.key))
+            }
+            PinType::Project => {
+                let project_id = pin.project_id.ok_or_else(|| {
+                    anyhow::anyhow!("invalid state: project pin without a project")
+                })?;
+                let project = self
+                    .get_project_by_id(project_id)?
+                    .ok_or_else(|| anyhow::anyhow!("project not found"))?;
+                Ok((project.name, None))
+            }
+            PinType::View | PinType::Local => {
+                let view_id = pin
+                    .view_id
+                    .ok_or_else(|| anyhow::anyhow!("invalid state: view pin without a view"))?;
+                let view = self
+                    .get_view_by_id(view_id)?
+                    .ok_or_else(|| anyhow::anyhow!("view not found"))?;
+                let project = self
+                    .get_project_by_id(view.project_id)?
+                    .ok_or_else(|| anyhow::anyhow!("project not found"))?;
+                Ok((project.name, Some(view.name)))
+            }
+        }
+    }
+
+    /// Collect the projects, views and pins into a document that can be
+    /// imported again with [`Repository::import_state`].
+    pub fn export_state(&self) -> Result<State> {
+        let mut projects = Vec::new();
+        for project in self.list_projects(SortOrder::Created)? {
+            let mut views = Vec::new();
+            for view in self.list_views_for_project(&project, SortOrder::Position)? {
+                views.push(ViewState {
+                    active: view.id == project.active_view_id,
+                    commands: self.list_view_commands(&view)?,
+                    layout: self.get_view_layout(&view)?,
+                    name: view.name,
+                    position: view.position,
+                });
+            }
+            projects.push(ProjectState {
+                root: self.get_project_root(&project)?,
+                tmux_session: self.get_project_tmux_session(&project)?,
+                env: self.list_project_env(&project)?.into_iter().collect(),
+                name: project.name,
+                views,
+            });
+        }
+
+        let mut pins = Vec::new();
+        let mut stacks = Vec::new();
+        let mut external_pins = Vec::new();
+        for pin in self.list_pins()? {
+            if pin.pin_type.is_external() {
+                external_pins.push(ExternalPinState {
+                    target: pin.target.unwrap_or_default(),
+                    key: pin.key,
+                    pin_type: pin.pin_type,
+                });
+                continue;
+            }
+            if pin.pin_type == PinType::Stack {
+                let mut views = Vec::new();
+                for view in self.list_pin_stack_views(&pin)? {
+                    let project = self
+                        .get_project_by_id(view.project_id)?
+                        .ok_or_else(|| anyhow::anyhow!("project not found"))?;
+                    views.push(StackViewState {
+                        project: project.name,
+                        view: view.name,
+                    });
+                }
+                stacks.push(StackState {
+                    key: pin.key,
+                    views,
+                });
+                continue;
+            }
+            let (project, view) = self.get_pin_target_names(&pin)?;
+            pins.push(PinState {
+                key: pin.key,
+                pin_type: pin.pin_type,
+                project,
+                view,
+            });
+        }
+
+        Ok(State {
+            version: STATE_VERSION,
+            projects,
+            pins,
+            stacks,
+            external_pins,
+        })
+    }
+
+    /// Describe why the document can not be imported with the given mode:
+    /// problems within the document, and, when merging, projects and global
+    /// pin keys that already exist.
+    pub fn import_conflicts(&self, state: &State, mode: ImportMode) -> Result<Vec<String>> {
+        let mut conflicts = state.conflicts();
+        if mode == ImportMode::Merge {
+            for project in &state.projects {
+                if self.get_project_by_name(&project.name)?.is_some() {
+                    conflicts.push(format!("project '{}' already exists", project.name));
+                }
+            }
+            for pin in self.list_pins()? {
+                if pin.pin_type != PinType::Local
+                    && (state
+                        .pins
+                        .iter()
+                        .any(|p| p.pin_type != PinType::Local && p.key == pin.key)
+                        || state.stacks.iter().any(|s| s.key == pin.key)
+                        || state.external_pins.iter().any(|p| p.key == pin.key))
+                {
+                    conflicts.push(format!("pin key '{}' is already in use", pin.key));
+                }
+            }
+        }
+        Ok(conflicts)
+    }
+
+    /// Import a document in a single transaction. When replacing, all
+    /// existing projects, views and pins are removed first, keeping their
+    /// focus time for reports. Nothing is changed when the document has conflicts.
+    pub fn import_state(&mut self, state: &State, mode: ImportMode) -> Result<()> {
+        let description = format!("import {} project(s)", state.projects.len());
+        self.journaled(description, |repo| {
+            let conflicts = repo.import_conflicts(state, mode)?;
+            if !conflicts.is_empty() {
+                return Err(anyhow::anyhow!(
+                    "the document can not be imported:\n  {}",
+                    conflicts.join("\n  ")
+                ));
+            }
+
+            let tx = repo.conn.savepoint()?;
+
+            if mode == ImportMode::Replace {
+                tx.execute(&detach_focus_sessions_sql("1"), [])?;
+                tx.execute_batch(
+                    "DELETE FROM pin_stack_members;
+                     DELETE FROM pins;
+                     DELETE FROM view_commands;
+                     DELETE FROM project_env;
+                     DELETE FROM views;
+                     DELETE FROM projects;",
+                )?;
+            }
+
+            // the ids of the imported views, by project and view name
+            let mut view_ids = HashMap::new();
+            let mut project_ids = HashMap::new();
+            for project in &state.projects {
+                tx.execute(
+                    "INSERT INTO projects (name, active_view_id, root, tmux_session)
+                     VALUES (?1, 0, ?2, ?3)",
+                    params![project.name, project.root, project.tmux_session],
+                )?;
+                let project_id = tx.last_insert_rowid();
+                project_ids.insert(project.name.as_str(), project_id);
+
+                for view in &project.views {
+                    tx.execute(
+                        "INSERT INTO views (name, project_id, position, layout) VALUES (?1, ?2, ?3, ?4)",
+                        params![view.name, project_id, view.position, view.layout],
+                    )?;
+                    let view_id = tx.last_insert_rowid();
+                    view_ids.insert((project.name.as_str(), view.name.as_str()), view_id);
+
+                    for (position, command) in view.commands.iter().enumerate() {
+                        tx.execute(
+                            "INSERT INTO view_commands (view_id, position, command) VALUES (?1, ?2, ?3)",
+                            params![view_id, position as i64, command],
+                        )?;
+                    }
+                }
+
+                let active_view = project
+                    .active_view()
+                    .ok_or_else(|| anyhow::anyhow!("a project needs at least one view"))?;
+                tx.execute(
+                    "UPDATE projects SET active_view_id = ?1 WHERE id = ?2",
+                    params![
+                        view_ids[&(project.name.as_str(), active_view.name.as_str())],
+                        project_id
+                    ],
+                )?;
+
+                for (name, value) in &project.env {
+                    tx.execute(
+                        "INSERT INTO project_env (project_id, name, value) VALUES (?1, ?2, ?3)",
+                        params![project_id, name, value],
+                    )?;
+                }
+            }
+
+            for pin in &state.pins {
+                let project_id = project_ids[pin.project.as_str()];
+                let view_id = pin
+                    .view
+                    .as_deref()
+                    .map(|view| view_ids[&(pin.project.as_str(), view)]);
+                match pin.pin_type {
+                    PinType::Project => tx.execute(
+                        "INSERT INTO pins (key, pin_type, project_id) VALUES (?1, ?2, ?3)",
+                        params![pin.key, pin.pin_type, project_id],
+                    )?,
+                    PinType::View => tx.execute(
+                        "INSERT INTO pins (key, pin_type, view_id) VALUES (?1, ?2, ?3)",
+                        params![pin.key, pin.pin_type, view_id],
+                    )?,
+                    PinType::Local => tx.execute(
+                        "INSERT INTO pins (key, pin_type, view_id, project_id) VALUES (?1, ?2, ?3, ?4)",
+                        params![pin.key, pin.pin_type, view_id, project_id],
+                    )?,
+                    PinType::Stack | PinType::Workspace | PinType::Window | PinType::Mark => {
+                        return Err(anyhow::anyhow!(
+                            "pin '{}' does not point to a project or view",
+                            pin.key
+                        ));
+                    }
+                };
+            }
+
+            for pin in &state.external_pins {
+                tx.execute(
+                    "INSERT INTO pins (key, pin_type, target) VALUES (?1, ?2, ?3)",
+                    params![pin.key, pin.pin_type, pin.target],
+                )?;
+            }
+
+            for stack in &state.stacks {
+                tx.execute(
+                    "INSERT INTO pins (key, pin_type) VALUES (?1, ?2)",
+                    params![stack.key, PinType::Stack],
+                )?;
+                let pin_id = tx.last_insert_rowid();
+                for (position, member) in stack.views.iter().enumerate() {
+                    tx.execute(
+                        "INSERT INTO pin_stack_members (pin_id, view_id, position) VALUES (?1, ?2, ?3)",
+                        params![
+                            pin_id,
+                            view_ids[&(member.project.as_str(), member.view.as_str())],
+                            position as i64
+                        ],
+                    )?;
+                }
+            }
+
+            tx.commit()?;
+            Ok(())
+        })
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{PinType, Project, Repository, View};
+    use super::{
+        ImportMode, MIGRATIONS, NameMatch, PinTarget, PinType, Project, ReportGrouping, Repository,
+        SortOrder, View, parse_view_path, resolve_name, sanitize_project_name,
+    };
     use rusqlite::Connection;
 
+    #[test]
+    fn test_resolve_name_prefers_exact_match() {
+        let names = ["infra", "infrastructure-terraform"];
+        let resolved = resolve_name("infra", &names, NameMatch::Fuzzy).unwrap();
+        assert_eq!(resolved, Some("infra"));
+    }
+
+    #[test]
+    fn test_resolve_name_with_unique_prefix() {
+        let names = ["infrastructure-terraform", "muxwm"];
+        let resolved = resolve_name("infra", &names, NameMatch::Fuzzy).unwrap();
+        assert_eq!(resolved, Some("infrastructure-terraform"));
+    }
+
+    #[test]
+    fn test_resolve_name_prefix_wins_over_substring() {
+        let names = ["web", "old-web-app"];
+        let resolved = resolve_name("we", &names, NameMatch::Fuzzy).unwrap();
+        assert_eq!(resolved, Some("web"));
+    }
+
+    #[test]
+    fn test_resolve_name_with_subsequence_ignoring_case() {
+        let names = ["Infrastructure-Terraform", "muxwm"];
+        let resolved = resolve_name("itf", &names, NameMatch::Fuzzy).unwrap();
+        assert_eq!(resolved, Some("Infrastructure-Terraform"));
+    }
+
+    #[test]
+    fn test_resolve_name_when_ambiguous_lists_candidates() {
+        let names = ["infra-aws", "infra-gcp", "muxwm"];
+        let err = resolve_name("infra", &names, NameMatch::Fuzzy).unwrap_err();
+        assert!(err.to_string().contains("infra-aws, infra-gcp"));
+    }
+
+    #[test]
+    fn test_resolve_name_when_nothing_matches() {
+        let names = ["muxwm"];
+        let resolved = resolve_name("xyz", &names, NameMatch::Fuzzy).unwrap();
+        assert!(resolved.is_none());
+    }
+
+    #[test]
+    fn test_resolve_name_exact_ignores_prefixes() {
+        let names = ["infrastructure-terraform"];
+        let resolved = resolve_name("infra", &names, NameMatch::Exact).unwrap();
+        assert!(resolved.is_none());
+    }
+
+    #[test]
+    fn test_new_applies_all_migrations() {
+        let conn = Connection::open_in_memory().unwrap();
+        let repo = Repository::new(conn).unwrap();
+
+        let version: i64 = repo
+            .conn
+            .pragma_query_value(None, "user_version", |row| row.get(0))
+            .unwrap();
+        assert_eq!(version as usize, MIGRATIONS.len());
+    }
+
+    #[test]
+    fn test_new_is_idempotent_on_an_existing_database() {
+        let mut repo = Repository::new(Connection::open_in_memory().unwrap()).unwrap();
+        repo.create_project("proj1").unwrap();
+
+        // reopening must not try to re-apply migrations
+        let repo = Repository::new(repo.conn).unwrap();
+        assert_eq!(repo.list_projects(SortOrder::Created).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_new_migrates_pins_of_an_unversioned_database() {
+        let conn = Connection::open_in_memory().unwrap();
+        // the schema as it was before versioning, with a pin in it
+        conn.execute_batch(
+            r#"
+            CREATE TABLE views (
+                id    INTEGER PRIMARY KEY,
+                name  TEXT NOT NULL,
+                project_id INTEGER NOT NULL,
+                position INTEGER NOT NULL,
+                UNIQUE(project_id, position)
+                UNIQUE(project_id, name)
+            );
+            CREATE TABLE projects (
+                id    INTEGER PRIMARY KEY,
+                name  TEXT NOT NULL UNIQUE,
+                active_view_id INTEGER not null
+            );
+            CREATE TABLE pins (
+                id INTEGER PRIMARY KEY,
+                key TEXT NOT NULL UNIQUE,
+                pin_type TEXT NOT NULL,
+                view_id INTEGER,
+                project_id INTEGER
+            );
+            INSERT INTO projects (id, name, active_view_id) VALUES (1, 'proj1', 1);
+            INSERT INTO views (id, name, project_id, position) VALUES (1, 'view0', 1, 0);
+            INSERT INTO pins (key, pin_type, view_id) VALUES ('g', 'view', 1);
+            "#,
+        )
+        .unwrap();
+
+        let repo = Repository::new(conn).unwrap();
+        let view = repo.get_view_for_pin_key("g", None).unwrap().unwrap();
+        assert_eq!(view.id, 1);
+    }
+
+    #[test]
+    fn test_new_keeps_pin_stacks_when_rebuilding_pins() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+        let proj1 = repo.create_project("proj1").unwrap();
+        let proj1_view0 = repo.get_active_view_for_project(&proj1).unwrap();
+        repo.add_view_to_pin_stack("t", &proj1_view0).unwrap();
+
+        // reapply the migrations from the one that rebuilds the pins table
+        // with external pins
+        repo.conn.pragma_update(None, "user_version", 9).unwrap();
+        let repo = Repository::new(repo.conn).unwrap();
+
+        let view = repo.get_view_for_pin_key("t", None).unwrap().unwrap();
+        assert_eq!(view, proj1_view0);
+    }
+
     #[test]
     fn test_create_project_creates_project_and_active_view() {
         let conn = Connection::open_in_memory().unwrap();
@@ -613,6 +3071,193 @@ mod tests {
         assert!(repo.create_project("proj1").is_err());
     }
 
+    #[test]
+    fn test_create_project_with_views_creates_views_in_order() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let views = ["code", "term", "docs"].map(String::from);
+        let project = repo
+            .create_project_with_views("proj1", &views, Some("term"))
+            .unwrap();
+
+        let names = repo
+            .list_views_for_project(&project, SortOrder::Position)
+            .unwrap()
+            .iter()
+            .map(|v| v.name().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(names, views);
+
+        let active = repo.get_active_view_for_project(&project).unwrap();
+        assert_eq!(active.name, "term");
+    }
+
+    #[test]
+    fn test_create_project_with_views_defaults_to_first_view_as_active() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let views = ["code", "term"].map(String::from);
+        let project = repo
+            .create_project_with_views("proj1", &views, None)
+            .unwrap();
+
+        let active = repo.get_active_view_for_project(&project).unwrap();
+        assert_eq!(active.name, "code");
+    }
+
+    #[test]
+    fn test_open_project_when_project_exists() {
+        let mut repo = repo_with_state();
+        let proj = repo.get_project_by_name("proj1").unwrap().unwrap();
+        let view = repo
+            .find_view_in_project_by_name(&proj, "view0", NameMatch::Exact)
+            .unwrap()
+            .unwrap();
+        repo.set_active_view_for_project(&proj, &view).unwrap();
+        let projects = repo.list_projects(SortOrder::Created).unwrap().len();
+
+        let views = ["code"].map(String::from);
+        let (opened, active, created) = repo
+            .open_project("proj1", NameMatch::Exact, Some(&views), None)
+            .unwrap();
+        assert!(!created);
+        assert_eq!(opened.name(), "proj1");
+        assert_eq!(active, view);
+        assert_eq!(
+            repo.list_projects(SortOrder::Created).unwrap().len(),
+            projects
+        );
+        // the views given for creation are ignored
+        assert!(
+            repo.find_view_in_project_by_name(&opened, "code", NameMatch::Exact)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_open_project_when_project_is_new() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let views = ["code", "term", "docs"].map(String::from);
+        let (project, active, created) = repo
+            .open_project("proj1", NameMatch::Fuzzy, Some(&views), Some("term"))
+            .unwrap();
+        assert!(created);
+        assert_eq!(project.name(), "proj1");
+        assert_eq!(active.name(), "term");
+        let names = repo
+            .list_views_for_project(&project, SortOrder::Position)
+            .unwrap()
+            .iter()
+            .map(|v| v.name().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(names, views);
+
+        let (project, active, created) = repo
+            .open_project("proj2", NameMatch::Fuzzy, None, None)
+            .unwrap();
+        assert!(created);
+        assert_eq!(project.name(), "proj2");
+        assert_eq!(active.name(), "view0");
+    }
+
+    #[test]
+    fn test_open_project_with_a_prefix_of_an_existing_name() {
+        let mut repo = repo_with_state();
+
+        let (project, _, created) = repo
+            .open_project("oj2", NameMatch::Fuzzy, None, None)
+            .unwrap();
+        assert!(!created);
+        assert_eq!(project.name(), "proj2");
+
+        let (project, _, created) = repo
+            .open_project("proj", NameMatch::Exact, None, None)
+            .unwrap();
+        assert!(created);
+        assert_eq!(project.name(), "proj");
+    }
+
+    #[test]
+    fn test_create_project_with_views_when_active_view_is_unknown() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let views = ["code"].map(String::from);
+        assert!(
+            repo.create_project_with_views("proj1", &views, Some("term"))
+                .is_err()
+        );
+        assert!(repo.create_project_with_views("proj1", &[], None).is_err());
+    }
+
+    #[test]
+    fn test_create_project_with_views_is_atomic() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        // the duplicate view name fails the creation half way through
+        let views = ["code", "code"].map(String::from);
+        assert!(
+            repo.create_project_with_views("proj1", &views, None)
+                .is_err()
+        );
+        assert!(repo.get_project_by_name("proj1").unwrap().is_none());
+        assert!(repo.list_views(SortOrder::Created).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_delete_project_removes_everything_attached_to_it() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let proj1 = repo.create_project("proj1").unwrap();
+        let proj1_view0 = repo.get_active_view_for_project(&proj1).unwrap();
+        let proj1_view1 = repo.create_view_in_project(&proj1, "view1").unwrap();
+        repo.upsert_pin_for_project("p", &proj1, false).unwrap();
+        repo.upsert_pin_for_view("v", &proj1_view1, false).unwrap();
+        repo.upsert_local_pin_for_view("1", &proj1_view0, false)
+            .unwrap();
+        repo.add_view_command(&proj1_view0, "alacritty").unwrap();
+        repo.set_project_env(&proj1, "A", "1").unwrap();
+        repo.mark_view_focused_at(&proj1_view0, 100).unwrap();
+
+        let proj2 = repo.create_project("proj2").unwrap();
+        let proj2_view0 = repo.get_active_view_for_project(&proj2).unwrap();
+        repo.upsert_pin_for_view("w", &proj2_view0, false).unwrap();
+        repo.mark_view_focused_at(&proj2_view0, 200).unwrap();
+
+        repo.delete_project(&proj1).unwrap();
+
+        assert!(repo.get_project_by_id(proj1.id).unwrap().is_none());
+        assert!(repo.get_view_by_id(proj1_view1.id).unwrap().is_none());
+        assert!(repo.list_view_commands(&proj1_view0).unwrap().is_empty());
+        assert!(repo.list_project_env(&proj1).unwrap().is_empty());
+
+        // the other project is left alone
+        let pins = repo.list_pins().unwrap();
+        assert_eq!(pins.len(), 1);
+        assert_eq!(pins[0].key(), "w");
+        assert_eq!(repo.list_views(SortOrder::Created).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_delete_project_when_project_is_not_found() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let proj1 = Project {
+            id: 1,
+            name: "proj1".to_string(),
+            active_view_id: 1,
+        };
+        assert!(repo.delete_project(&proj1).is_err());
+    }
+
     #[test]
     fn test_list_projects_when_there_are_projects() {
         let conn = Connection::open_in_memory().unwrap();
@@ -625,22 +3270,69 @@ mod tests {
         // so confirm that on creation.
         assert!(proj1.id < proj2.id);
 
-        let projects = repo.list_projects().unwrap();
+        let projects = repo.list_projects(SortOrder::Created).unwrap();
         assert_eq!(projects.len(), 2);
         assert_eq!(projects[0].name, "proj1");
         assert_eq!(projects[1].name, "proj2");
     }
 
+    #[test]
+    fn test_project_contains_view() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let proj1 = repo.create_project("proj1").unwrap();
+        let proj2 = repo.create_project("proj2").unwrap();
+        let proj1_view = repo.get_active_view_for_project(&proj1).unwrap();
+
+        assert!(proj1.contains_view(&proj1_view));
+        assert!(!proj2.contains_view(&proj1_view));
+    }
+
     #[test]
     fn test_list_projects_when_there_are_no_projects() {
         let conn = Connection::open_in_memory().unwrap();
         let repo = Repository::new(conn).unwrap();
 
-        let projects = repo.list_projects();
+        let projects = repo.list_projects(SortOrder::Created);
         assert!(projects.is_ok());
         assert_eq!(projects.unwrap().len(), 0);
     }
 
+    #[test]
+    fn test_list_projects_by_most_recently_used() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let proj1 = repo.create_project("proj1").unwrap();
+        let proj2 = repo.create_project("proj2").unwrap();
+        let _ = repo.create_project("proj3").unwrap();
+
+        let proj1_view = repo.get_active_view_for_project(&proj1).unwrap();
+        let proj2_view = repo.get_active_view_for_project(&proj2).unwrap();
+        repo.mark_view_focused_at(&proj2_view, 100).unwrap();
+        repo.mark_view_focused_at(&proj1_view, 200).unwrap();
+
+        // never focused projects come last
+        let projects = repo.list_projects(SortOrder::MostRecentlyUsed).unwrap();
+        let names = projects.iter().map(|p| p.name()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["proj1", "proj2", "proj3"]);
+    }
+
+    #[test]
+    fn test_list_projects_by_name() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let _ = repo.create_project("b").unwrap();
+        let _ = repo.create_project("c").unwrap();
+        let _ = repo.create_project("a").unwrap();
+
+        let projects = repo.list_projects(SortOrder::Name).unwrap();
+        let names = projects.iter().map(|p| p.name()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["a", "b", "c"]);
+    }
+
     #[test]
     fn test_get_project_by_id_when_projects_is_found() {
         let conn = Connection::open_in_memory().unwrap();
@@ -692,84 +3384,222 @@ mod tests {
     }
 
     #[test]
-    fn test_list_views_for_project_when_views_are_found() {
+    fn test_find_project_by_name_with_prefix() {
         let conn = Connection::open_in_memory().unwrap();
         let mut repo = Repository::new(conn).unwrap();
 
-        let proj1 = repo.create_project("proj1").unwrap();
-        let proj1_view0 = repo.get_active_view_for_project(&proj1).unwrap();
-        let proj1_view1 = repo.create_view_in_project(&proj1, "view1").unwrap();
-        let proj1_view2 = repo.create_view_in_project(&proj1, "view2").unwrap();
+        let proj = repo.create_project("infrastructure-terraform").unwrap();
+        let _ = repo.create_project("muxwm").unwrap();
 
-        let views = repo.list_views_for_project(&proj1).unwrap();
-        assert_eq!(views.len(), 3);
-        assert_eq!(views[0], proj1_view0);
-        assert_eq!(views[1], proj1_view1);
-        assert_eq!(views[2], proj1_view2);
+        let found = repo
+            .find_project_by_name("infra", NameMatch::Fuzzy)
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.id, proj.id);
+
+        let found = repo
+            .find_project_by_name("infra", NameMatch::Exact)
+            .unwrap();
+        assert!(found.is_none());
     }
 
     #[test]
-    fn test_list_views_for_project_when_views_are_not_found() {
+    fn test_set_project_root() {
         let conn = Connection::open_in_memory().unwrap();
-        let repo = Repository::new(conn).unwrap();
+        let mut repo = Repository::new(conn).unwrap();
 
-        let project = Project {
-            id: 1,
-            name: "proj1".to_string(),
-            active_view_id: 1,
-        };
+        let proj1 = repo.create_project("proj1").unwrap();
+        assert!(repo.get_project_root(&proj1).unwrap().is_none());
+
+        repo.set_project_root(&proj1, Some("/src/proj1")).unwrap();
+        let root = repo.get_project_root(&proj1).unwrap();
+        assert_eq!(root.as_deref(), Some("/src/proj1"));
 
-        assert!(repo.list_views_for_project(&project).is_err());
+        repo.set_project_root(&proj1, None).unwrap();
+        assert!(repo.get_project_root(&proj1).unwrap().is_none());
     }
 
     #[test]
-    fn test_get_active_view_for_project_when_project_is_found() {
+    fn test_get_project_by_root() {
         let conn = Connection::open_in_memory().unwrap();
         let mut repo = Repository::new(conn).unwrap();
 
         let proj1 = repo.create_project("proj1").unwrap();
         let _ = repo.create_project("proj2").unwrap();
+        repo.set_project_root(&proj1, Some("/src/proj1")).unwrap();
 
-        let proj1_active_view = repo.get_active_view_for_project(&proj1).unwrap();
-        assert_eq!(proj1_active_view.name, "view0");
-        assert_eq!(proj1_active_view.id, proj1.active_view_id);
+        let found = repo.get_project_by_root("/src/proj1").unwrap().unwrap();
+        assert_eq!(found.id, proj1.id);
+        assert!(repo.get_project_by_root("/src/proj2").unwrap().is_none());
     }
 
     #[test]
-    fn test_get_active_view_for_project_when_project_is_not_found() {
+    fn test_sanitize_project_name() {
+        assert_eq!(sanitize_project_name("muxwm"), "muxwm");
+        assert_eq!(sanitize_project_name(" issue#12 fix "), "issue-12-fix");
+    }
+
+    #[test]
+    fn test_set_project_root_when_project_is_not_found() {
         let conn = Connection::open_in_memory().unwrap();
-        let repo = Repository::new(conn).unwrap();
+        let mut repo = Repository::new(conn).unwrap();
 
-        let fake_project = Project {
+        let proj1 = Project {
             id: 1,
             name: "proj1".to_string(),
             active_view_id: 1,
         };
-
-        assert!(repo.get_active_view_for_project(&fake_project).is_err());
+        assert!(repo.set_project_root(&proj1, Some("/src")).is_err());
     }
 
     #[test]
-    fn test_crate_view_in_project_when_project_is_found() {
+    fn test_set_project_tmux_session() {
         let conn = Connection::open_in_memory().unwrap();
         let mut repo = Repository::new(conn).unwrap();
 
         let proj1 = repo.create_project("proj1").unwrap();
-        let view = repo.create_view_in_project(&proj1, "view2").unwrap();
-        assert_eq!(view.name, "view2");
-        assert_eq!(view.project_id, proj1.id);
-        assert_eq!(view.position, 1);
+        assert!(repo.get_project_tmux_session(&proj1).unwrap().is_none());
+
+        repo.set_project_tmux_session(&proj1, Some("work")).unwrap();
+        let session = repo.get_project_tmux_session(&proj1).unwrap();
+        assert_eq!(session.as_deref(), Some("work"));
+
+        repo.set_project_tmux_session(&proj1, None).unwrap();
+        assert!(repo.get_project_tmux_session(&proj1).unwrap().is_none());
     }
 
     #[test]
-    fn test_create_view_in_project_when_project_is_not_found() {
+    fn test_set_project_env() {
         let conn = Connection::open_in_memory().unwrap();
         let mut repo = Repository::new(conn).unwrap();
 
-        let proj = Project {
-            id: 1,
-            name: "proj1".to_string(),
-            active_view_id: 1,
+        let proj1 = repo.create_project("proj1").unwrap();
+        let proj2 = repo.create_project("proj2").unwrap();
+
+        repo.set_project_env(&proj1, "B", "1").unwrap();
+        repo.set_project_env(&proj1, "A", "2").unwrap();
+        repo.set_project_env(&proj2, "A", "3").unwrap();
+        // setting a variable again overwrites it
+        repo.set_project_env(&proj1, "B", "4").unwrap();
+
+        let env = repo.list_project_env(&proj1).unwrap();
+        assert_eq!(
+            env,
+            vec![
+                ("A".to_string(), "2".to_string()),
+                ("B".to_string(), "4".to_string())
+            ]
+        );
+
+        repo.unset_project_env(&proj1, "A").unwrap();
+        let env = repo.list_project_env(&proj1).unwrap();
+        assert_eq!(env, vec![("B".to_string(), "4".to_string())]);
+        assert_eq!(repo.list_project_env(&proj2).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_list_views_for_project_when_views_are_found() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let proj1 = repo.create_project("proj1").unwrap();
+        let proj1_view0 = repo.get_active_view_for_project(&proj1).unwrap();
+        let proj1_view1 = repo.create_view_in_project(&proj1, "view1").unwrap();
+        let proj1_view2 = repo.create_view_in_project(&proj1, "view2").unwrap();
+
+        let views = repo
+            .list_views_for_project(&proj1, SortOrder::Position)
+            .unwrap();
+        assert_eq!(views.len(), 3);
+        assert_eq!(views[0], proj1_view0);
+        assert_eq!(views[1], proj1_view1);
+        assert_eq!(views[2], proj1_view2);
+    }
+
+    #[test]
+    fn test_list_views_for_project_by_most_recently_used() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let proj1 = repo.create_project("proj1").unwrap();
+        let proj1_view0 = repo.get_active_view_for_project(&proj1).unwrap();
+        let proj1_view1 = repo.create_view_in_project(&proj1, "view1").unwrap();
+        let proj1_view2 = repo.create_view_in_project(&proj1, "view2").unwrap();
+
+        repo.mark_view_focused_at(&proj1_view2, 100).unwrap();
+        repo.mark_view_focused_at(&proj1_view0, 200).unwrap();
+
+        let views = repo
+            .list_views_for_project(&proj1, SortOrder::MostRecentlyUsed)
+            .unwrap();
+        assert_eq!(views, vec![proj1_view0, proj1_view2, proj1_view1]);
+    }
+
+    #[test]
+    fn test_list_views_for_project_when_views_are_not_found() {
+        let conn = Connection::open_in_memory().unwrap();
+        let repo = Repository::new(conn).unwrap();
+
+        let project = Project {
+            id: 1,
+            name: "proj1".to_string(),
+            active_view_id: 1,
+        };
+
+        assert!(
+            repo.list_views_for_project(&project, SortOrder::Position)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_get_active_view_for_project_when_project_is_found() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let proj1 = repo.create_project("proj1").unwrap();
+        let _ = repo.create_project("proj2").unwrap();
+
+        let proj1_active_view = repo.get_active_view_for_project(&proj1).unwrap();
+        assert_eq!(proj1_active_view.name, "view0");
+        assert_eq!(proj1_active_view.id, proj1.active_view_id);
+    }
+
+    #[test]
+    fn test_get_active_view_for_project_when_project_is_not_found() {
+        let conn = Connection::open_in_memory().unwrap();
+        let repo = Repository::new(conn).unwrap();
+
+        let fake_project = Project {
+            id: 1,
+            name: "proj1".to_string(),
+            active_view_id: 1,
+        };
+
+        assert!(repo.get_active_view_for_project(&fake_project).is_err());
+    }
+
+    #[test]
+    fn test_crate_view_in_project_when_project_is_found() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let proj1 = repo.create_project("proj1").unwrap();
+        let view = repo.create_view_in_project(&proj1, "view2").unwrap();
+        assert_eq!(view.name, "view2");
+        assert_eq!(view.project_id, proj1.id);
+        assert_eq!(view.position, 1);
+    }
+
+    #[test]
+    fn test_create_view_in_project_when_project_is_not_found() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let proj = Project {
+            id: 1,
+            name: "proj1".to_string(),
+            active_view_id: 1,
         };
 
         let view = repo.create_view_in_project(&proj, "view");
@@ -788,6 +3618,182 @@ mod tests {
         assert!(view.is_err());
     }
 
+    #[test]
+    fn test_get_view_at_index_in_project() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let proj1 = repo.create_project("proj1").unwrap();
+        let proj1_view0 = repo.get_active_view_for_project(&proj1).unwrap();
+        let proj1_view1 = repo.create_view_in_project(&proj1, "view1").unwrap();
+
+        let view = repo.get_view_at_index_in_project(&proj1, 1).unwrap();
+        assert_eq!(view, Some(proj1_view0));
+        let view = repo.get_view_at_index_in_project(&proj1, 2).unwrap();
+        assert_eq!(view, Some(proj1_view1));
+    }
+
+    #[test]
+    fn test_get_view_at_index_in_project_when_index_is_out_of_range() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let proj1 = repo.create_project("proj1").unwrap();
+
+        assert!(
+            repo.get_view_at_index_in_project(&proj1, 0)
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            repo.get_view_at_index_in_project(&proj1, 2)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_find_view_in_project_by_name() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let proj1 = repo.create_project("proj1").unwrap();
+        let proj1_term = repo.create_view_in_project(&proj1, "terminal").unwrap();
+        let proj2 = repo.create_project("proj2").unwrap();
+        let _ = repo.create_view_in_project(&proj2, "term").unwrap();
+
+        // views of other projects are not candidates
+        let view = repo
+            .find_view_in_project_by_name(&proj1, "term", NameMatch::Fuzzy)
+            .unwrap();
+        assert_eq!(view, Some(proj1_term));
+
+        let view = repo
+            .find_view_in_project_by_name(&proj1, "term", NameMatch::Exact)
+            .unwrap();
+        assert!(view.is_none());
+    }
+
+    #[test]
+    fn test_parse_view_path() {
+        assert_eq!(parse_view_path("proj1/view1"), (Some("proj1"), "view1"));
+        assert_eq!(parse_view_path("view1"), (None, "view1"));
+        assert_eq!(
+            parse_view_path("proj1/docs/api"),
+            (Some("proj1"), "docs/api")
+        );
+        assert_eq!(parse_view_path("/view1"), (Some(""), "view1"));
+    }
+
+    #[test]
+    fn test_find_view_by_path() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let proj1 = repo.create_project("proj1").unwrap();
+        let proj1_docs = repo.create_view_in_project(&proj1, "docs").unwrap();
+        let proj1_api = repo.create_view_in_project(&proj1, "docs/api").unwrap();
+        let proj2 = repo.create_project("proj2").unwrap();
+        let proj2_docs = repo.create_view_in_project(&proj2, "docs").unwrap();
+
+        // a bare view name is looked up in the current project
+        let view = repo
+            .find_view_by_path("docs", Some(&proj2), NameMatch::Exact)
+            .unwrap();
+        assert_eq!(view, Some(proj2_docs));
+        assert!(
+            repo.find_view_by_path("docs", None, NameMatch::Exact)
+                .is_err()
+        );
+
+        let view = repo
+            .find_view_by_path("proj1/docs", Some(&proj2), NameMatch::Exact)
+            .unwrap();
+        assert_eq!(view, Some(proj1_docs));
+
+        // only the first slash separates the project from the view
+        let view = repo
+            .find_view_by_path("proj1/docs/api", None, NameMatch::Exact)
+            .unwrap();
+        assert_eq!(view, Some(proj1_api));
+
+        let view = repo
+            .find_view_by_path("proj3/docs", Some(&proj1), NameMatch::Fuzzy)
+            .unwrap();
+        assert!(view.is_none());
+        let view = repo
+            .find_view_by_path("proj1/code", None, NameMatch::Exact)
+            .unwrap();
+        assert!(view.is_none());
+    }
+
+    #[test]
+    fn test_find_target() {
+        let mut repo = repo_with_state();
+        let proj1 = repo.get_project_by_name("proj1").unwrap().unwrap();
+        let proj1_view0 = repo
+            .find_view_in_project_by_name(&proj1, "view0", NameMatch::Exact)
+            .unwrap()
+            .unwrap();
+        let proj1_view1 = repo.get_active_view_for_project(&proj1).unwrap();
+        let proj2 = repo.get_project_by_name("proj2").unwrap().unwrap();
+        let proj2_view0 = repo.get_active_view_for_project(&proj2).unwrap();
+        repo.upsert_external_pin("b", PinType::Mark, "browser", false)
+            .unwrap();
+        repo.upsert_external_pin("w", PinType::Window, "94", false)
+            .unwrap();
+
+        let find = |view, project, pin, current| {
+            repo.find_target(view, project, pin, current, NameMatch::Fuzzy)
+                .unwrap()
+        };
+
+        // views, by path or as a view of the focused view's project
+        assert_eq!(
+            find(Some("proj1/view0"), None, None, None),
+            Some(PinTarget::View(proj1_view0.clone()))
+        );
+        assert_eq!(
+            find(Some("view1"), None, None, Some(&proj1_view0)),
+            Some(PinTarget::View(proj1_view1.clone()))
+        );
+        assert_eq!(find(Some("proj3/view0"), None, None, None), None);
+        assert_eq!(
+            find(None, None, None, Some(&proj2_view0)),
+            Some(PinTarget::View(proj2_view0.clone()))
+        );
+
+        // projects, onto their active view
+        assert_eq!(
+            find(None, Some("proj2"), None, None),
+            Some(PinTarget::View(proj2_view0.clone()))
+        );
+        assert_eq!(find(None, Some("proj3"), None, None), None);
+
+        // pins, of views, workspaces, windows and marks
+        assert_eq!(
+            find(None, None, Some("v"), None),
+            Some(PinTarget::View(proj1_view1))
+        );
+        assert_eq!(
+            find(None, None, Some("1"), Some(&proj1_view0)),
+            Some(PinTarget::View(proj1_view0.clone()))
+        );
+        assert_eq!(
+            find(None, None, Some("m"), None),
+            Some(PinTarget::Workspace("music".to_string()))
+        );
+        assert_eq!(
+            find(None, None, Some("w"), None),
+            Some(PinTarget::Window(94))
+        );
+        assert_eq!(
+            find(None, None, Some("b"), None),
+            Some(PinTarget::Mark("browser".to_string()))
+        );
+        assert_eq!(find(None, None, Some("x"), None), None);
+    }
+
     #[test]
     fn test_set_active_view_for_project_when_view_is_found() {
         let conn = Connection::open_in_memory().unwrap();
@@ -882,7 +3888,7 @@ mod tests {
     #[test]
     fn test_rename_view_when_view_is_not_found() {
         let conn = Connection::open_in_memory().unwrap();
-        let repo = Repository::new(conn).unwrap();
+        let mut repo = Repository::new(conn).unwrap();
 
         let view = View {
             id: 1,
@@ -922,7 +3928,7 @@ mod tests {
         let proj1_view1 = repo.create_view_in_project(&proj1, "view1").unwrap();
         let proj1_view2 = repo.create_view_in_project(&proj1, "view2").unwrap();
 
-        let views = repo.list_views().unwrap();
+        let views = repo.list_views(SortOrder::Created).unwrap();
         assert_eq!(views.len(), 3);
         assert_eq!(views[0], proj1_view0);
         assert_eq!(views[1], proj1_view1);
@@ -930,95 +3936,342 @@ mod tests {
     }
 
     #[test]
-    fn test_list_views_when_views_are_not_found() {
+    fn test_list_views_by_name() {
         let conn = Connection::open_in_memory().unwrap();
-        let repo = Repository::new(conn).unwrap();
+        let mut repo = Repository::new(conn).unwrap();
 
-        let views = repo.list_views();
-        assert!(views.is_ok());
-        assert_eq!(views.unwrap().len(), 0);
+        let proj2 = repo.create_project("proj2").unwrap();
+        let proj1 = repo.create_project("proj1").unwrap();
+        let proj2_view0 = repo.get_active_view_for_project(&proj2).unwrap();
+        let proj1_view0 = repo.get_active_view_for_project(&proj1).unwrap();
+        let proj1_a = repo.create_view_in_project(&proj1, "a").unwrap();
+
+        let views = repo.list_views(SortOrder::Name).unwrap();
+        assert_eq!(views, vec![proj1_a, proj1_view0, proj2_view0]);
     }
 
     #[test]
-    fn test_get_next_view_for_project_when_views_are_found() {
+    fn test_add_view_command_keeps_commands_in_order() {
         let conn = Connection::open_in_memory().unwrap();
         let mut repo = Repository::new(conn).unwrap();
 
         let proj1 = repo.create_project("proj1").unwrap();
         let proj1_view0 = repo.get_active_view_for_project(&proj1).unwrap();
         let proj1_view1 = repo.create_view_in_project(&proj1, "view1").unwrap();
-        let proj1_view2 = repo.create_view_in_project(&proj1, "view2").unwrap();
-
-        let next_view = repo.get_next_view_for_project(&proj1).unwrap();
-        assert_eq!(next_view, proj1_view1);
 
-        // update the active view and then reload the project since we just changed it
-        assert!(repo.set_active_view_for_project(&proj1, &next_view).is_ok());
-        let updated_proj1 = repo.get_project_by_id(proj1.id).unwrap().unwrap();
-        let next_view = repo.get_next_view_for_project(&updated_proj1).unwrap();
-        assert_eq!(next_view, proj1_view2);
+        repo.add_view_command(&proj1_view0, "alacritty").unwrap();
+        repo.add_view_command(&proj1_view0, "firefox").unwrap();
+        repo.add_view_command(&proj1_view1, "emacs").unwrap();
 
-        // one more time to make sure we cycle back to the first view
-        assert!(repo.set_active_view_for_project(&proj1, &next_view).is_ok());
-        let updated_proj1 = repo.get_project_by_id(proj1.id).unwrap().unwrap();
-        let next_view = repo.get_next_view_for_project(&updated_proj1).unwrap();
-        assert_eq!(next_view, proj1_view0);
+        let commands = repo.list_view_commands(&proj1_view0).unwrap();
+        assert_eq!(commands, vec!["alacritty", "firefox"]);
+        let commands = repo.list_view_commands(&proj1_view1).unwrap();
+        assert_eq!(commands, vec!["emacs"]);
     }
 
     #[test]
-    fn test_get_next_view_for_project_when_project_is_not_found() {
+    fn test_add_view_command_when_view_is_not_found() {
         let conn = Connection::open_in_memory().unwrap();
-        let repo = Repository::new(conn).unwrap();
+        let mut repo = Repository::new(conn).unwrap();
 
-        let project = repo.get_next_view_for_project(&Project {
+        let view = View {
             id: 1,
-            name: "proj1".to_string(),
-            active_view_id: 1,
-        });
-        assert!(project.is_err());
+            name: "view1".to_string(),
+            project_id: 1,
+            position: 1,
+        };
+        assert!(repo.add_view_command(&view, "alacritty").is_err());
     }
 
     #[test]
-    fn test_get_prev_view_for_project_when_views_are_found() {
+    fn test_clear_view_commands() {
         let conn = Connection::open_in_memory().unwrap();
         let mut repo = Repository::new(conn).unwrap();
 
         let proj1 = repo.create_project("proj1").unwrap();
-        let proj1_view0 = repo.get_active_view_for_project(&proj1).unwrap();
-        let proj1_view1 = repo.create_view_in_project(&proj1, "view1").unwrap();
-        let proj1_view2 = repo.create_view_in_project(&proj1, "view2").unwrap();
-
-        let prev_view = repo.get_prev_view_for_project(&proj1).unwrap();
-        assert_eq!(prev_view, proj1_view2);
-
-        // update the active view and then reload the project since we just changed it
-        assert!(repo.set_active_view_for_project(&proj1, &prev_view).is_ok());
-        let updated_proj1 = repo.get_project_by_id(proj1.id).unwrap().unwrap();
-        let prev_view = repo.get_prev_view_for_project(&updated_proj1).unwrap();
-        assert_eq!(prev_view, proj1_view1);
+        let view = repo.get_active_view_for_project(&proj1).unwrap();
+        repo.add_view_command(&view, "alacritty").unwrap();
 
-        // one more time to make sure we cycle back to the last view
-        assert!(repo.set_active_view_for_project(&proj1, &prev_view).is_ok());
-        let updated_proj1 = repo.get_project_by_id(proj1.id).unwrap().unwrap();
-        let prev_view = repo.get_prev_view_for_project(&updated_proj1).unwrap();
-        assert_eq!(prev_view, proj1_view0);
+        repo.clear_view_commands(&view).unwrap();
+        assert!(repo.list_view_commands(&view).unwrap().is_empty());
     }
 
     #[test]
-    fn test_get_prev_view_for_project_with_project_is_not_found() {
+    fn test_get_launch_commands_for_view_substitutes_names() {
         let conn = Connection::open_in_memory().unwrap();
-        let repo = Repository::new(conn).unwrap();
+        let mut repo = Repository::new(conn).unwrap();
 
-        let project = repo.get_prev_view_for_project(&Project {
-            id: 1,
-            name: "proj1".to_string(),
-            active_view_id: 1,
-        });
-        assert!(project.is_err());
+        let proj1 = repo.create_project("proj1").unwrap();
+        let view = repo.get_active_view_for_project(&proj1).unwrap();
+        repo.add_view_command(&view, "alacritty --title {project}/{view}")
+            .unwrap();
+
+        let commands = repo.get_launch_commands_for_view(&view).unwrap();
+        assert_eq!(commands, vec!["alacritty --title proj1/view0"]);
     }
 
     #[test]
-    fn test_get_window_manager_display_name_when_view_is_found() {
+    fn test_set_view_layout() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let proj1 = repo.create_project("proj1").unwrap();
+        let view = repo.get_active_view_for_project(&proj1).unwrap();
+        assert!(repo.get_view_layout(&view).unwrap().is_none());
+
+        repo.set_view_layout(&view, Some("{}")).unwrap();
+        assert_eq!(repo.get_view_layout(&view).unwrap().as_deref(), Some("{}"));
+
+        repo.set_view_layout(&view, None).unwrap();
+        assert!(repo.get_view_layout(&view).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_set_view_layout_when_view_is_not_found() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let view = View {
+            id: 1,
+            name: "view1".to_string(),
+            project_id: 1,
+            position: 1,
+        };
+        assert!(repo.set_view_layout(&view, Some("{}")).is_err());
+    }
+
+    #[test]
+    fn test_mark_view_focused_when_view_is_not_found() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let view = View {
+            id: 1,
+            name: "view1".to_string(),
+            project_id: 1,
+            position: 1,
+        };
+        assert!(repo.mark_view_focused(&view).is_err());
+    }
+
+    #[test]
+    fn test_focus_totals_by_project_and_view() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let proj1 = repo.create_project("proj1").unwrap();
+        let proj1_view0 = repo.get_active_view_for_project(&proj1).unwrap();
+        let proj1_view1 = repo.create_view_in_project(&proj1, "view1").unwrap();
+        let proj2 = repo.create_project("proj2").unwrap();
+        let proj2_view0 = repo.get_active_view_for_project(&proj2).unwrap();
+
+        repo.mark_view_focused_at(&proj1_view0, 100).unwrap();
+        repo.mark_view_focused_at(&proj1_view1, 110).unwrap();
+        repo.mark_view_focused_at(&proj2_view0, 150).unwrap();
+        repo.mark_view_focused_at(&proj1_view0, 160).unwrap();
+
+        let totals = repo.focus_totals(0, 200, ReportGrouping::Project).unwrap();
+        let totals: Vec<_> = totals
+            .iter()
+            .map(|t| (t.project(), t.view(), t.seconds()))
+            .collect();
+        assert_eq!(totals, vec![("proj1", None, 90), ("proj2", None, 10)]);
+
+        let totals = repo.focus_totals(0, 200, ReportGrouping::View).unwrap();
+        let totals: Vec<_> = totals
+            .iter()
+            .map(|t| (t.project(), t.view(), t.seconds()))
+            .collect();
+        assert_eq!(
+            totals,
+            vec![
+                ("proj1", Some("view0"), 50),
+                ("proj1", Some("view1"), 40),
+                ("proj2", Some("view0"), 10),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_focus_totals_clips_sessions_to_the_range() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let proj1 = repo.create_project("proj1").unwrap();
+        let proj1_view0 = repo.get_active_view_for_project(&proj1).unwrap();
+        let proj1_view1 = repo.create_view_in_project(&proj1, "view1").unwrap();
+
+        repo.mark_view_focused_at(&proj1_view0, 100).unwrap();
+        repo.mark_view_focused_at(&proj1_view1, 200).unwrap();
+
+        let totals = repo.focus_totals(150, 250, ReportGrouping::View).unwrap();
+        let totals: Vec<_> = totals.iter().map(|t| (t.view(), t.seconds())).collect();
+        assert_eq!(totals, vec![(Some("view0"), 50), (Some("view1"), 50)]);
+
+        // nothing was focused before the first session started
+        let totals = repo.focus_totals(0, 100, ReportGrouping::View).unwrap();
+        assert!(totals.is_empty());
+    }
+
+    #[test]
+    fn test_focus_totals_keep_the_time_of_deleted_projects() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let proj1 = repo.create_project("proj1").unwrap();
+        let proj1_view0 = repo.get_active_view_for_project(&proj1).unwrap();
+        let proj1_view1 = repo.create_view_in_project(&proj1, "view1").unwrap();
+        let proj2 = repo.create_project("proj2").unwrap();
+        let proj2_view0 = repo.get_active_view_for_project(&proj2).unwrap();
+
+        repo.mark_view_focused_at(&proj1_view0, 100).unwrap();
+        repo.mark_view_focused_at(&proj1_view1, 130).unwrap();
+        repo.mark_view_focused_at(&proj2_view0, 190).unwrap();
+        repo.delete_project(&proj1).unwrap();
+
+        let expected = vec![
+            ("proj1", Some("view1"), 60),
+            ("proj1", Some("view0"), 30),
+            ("proj2", Some("view0"), 10),
+        ];
+        let totals = repo.focus_totals(0, 200, ReportGrouping::View).unwrap();
+        let totals: Vec<_> = totals
+            .iter()
+            .map(|t| (t.project(), t.view(), t.seconds()))
+            .collect();
+        assert_eq!(totals, expected);
+
+        // an undo gives the sessions back to the restored views
+        repo.undo().unwrap().unwrap();
+        assert!(repo.get_view_by_id(proj1_view1.id).unwrap().is_some());
+        let totals = repo.focus_totals(0, 200, ReportGrouping::View).unwrap();
+        let totals: Vec<_> = totals
+            .iter()
+            .map(|t| (t.project(), t.view(), t.seconds()))
+            .collect();
+        assert_eq!(totals, expected);
+    }
+
+    #[test]
+    fn test_mark_view_focused_when_view_is_already_focused() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let proj1 = repo.create_project("proj1").unwrap();
+        let proj1_view0 = repo.get_active_view_for_project(&proj1).unwrap();
+
+        repo.mark_view_focused_at(&proj1_view0, 100).unwrap();
+        repo.mark_view_focused_at(&proj1_view0, 150).unwrap();
+
+        let sessions: i64 = repo
+            .conn
+            .query_row("SELECT COUNT(*) FROM focus_sessions", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(sessions, 1);
+        let totals = repo.focus_totals(0, 200, ReportGrouping::Project).unwrap();
+        assert_eq!(totals[0].seconds(), 100);
+    }
+
+    #[test]
+    fn test_timestamp_for_date_when_date_is_invalid() {
+        let conn = Connection::open_in_memory().unwrap();
+        let repo = Repository::new(conn).unwrap();
+
+        assert!(repo.timestamp_for_date("2024-01-01").is_ok());
+        assert!(repo.timestamp_for_date("2024-01-01 09:30").is_ok());
+        assert!(repo.timestamp_for_date("yesterday").is_err());
+    }
+
+    #[test]
+    fn test_list_views_when_views_are_not_found() {
+        let conn = Connection::open_in_memory().unwrap();
+        let repo = Repository::new(conn).unwrap();
+
+        let views = repo.list_views(SortOrder::Created);
+        assert!(views.is_ok());
+        assert_eq!(views.unwrap().len(), 0);
+    }
+
+    #[test]
+    fn test_get_next_view_for_project_when_views_are_found() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let proj1 = repo.create_project("proj1").unwrap();
+        let proj1_view0 = repo.get_active_view_for_project(&proj1).unwrap();
+        let proj1_view1 = repo.create_view_in_project(&proj1, "view1").unwrap();
+        let proj1_view2 = repo.create_view_in_project(&proj1, "view2").unwrap();
+
+        let next_view = repo.get_next_view_for_project(&proj1).unwrap();
+        assert_eq!(next_view, proj1_view1);
+
+        // update the active view and then reload the project since we just changed it
+        assert!(repo.set_active_view_for_project(&proj1, &next_view).is_ok());
+        let updated_proj1 = repo.get_project_by_id(proj1.id).unwrap().unwrap();
+        let next_view = repo.get_next_view_for_project(&updated_proj1).unwrap();
+        assert_eq!(next_view, proj1_view2);
+
+        // one more time to make sure we cycle back to the first view
+        assert!(repo.set_active_view_for_project(&proj1, &next_view).is_ok());
+        let updated_proj1 = repo.get_project_by_id(proj1.id).unwrap().unwrap();
+        let next_view = repo.get_next_view_for_project(&updated_proj1).unwrap();
+        assert_eq!(next_view, proj1_view0);
+    }
+
+    #[test]
+    fn test_get_next_view_for_project_when_project_is_not_found() {
+        let conn = Connection::open_in_memory().unwrap();
+        let repo = Repository::new(conn).unwrap();
+
+        let project = repo.get_next_view_for_project(&Project {
+            id: 1,
+            name: "proj1".to_string(),
+            active_view_id: 1,
+        });
+        assert!(project.is_err());
+    }
+
+    #[test]
+    fn test_get_prev_view_for_project_when_views_are_found() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let proj1 = repo.create_project("proj1").unwrap();
+        let proj1_view0 = repo.get_active_view_for_project(&proj1).unwrap();
+        let proj1_view1 = repo.create_view_in_project(&proj1, "view1").unwrap();
+        let proj1_view2 = repo.create_view_in_project(&proj1, "view2").unwrap();
+
+        let prev_view = repo.get_prev_view_for_project(&proj1).unwrap();
+        assert_eq!(prev_view, proj1_view2);
+
+        // update the active view and then reload the project since we just changed it
+        assert!(repo.set_active_view_for_project(&proj1, &prev_view).is_ok());
+        let updated_proj1 = repo.get_project_by_id(proj1.id).unwrap().unwrap();
+        let prev_view = repo.get_prev_view_for_project(&updated_proj1).unwrap();
+        assert_eq!(prev_view, proj1_view1);
+
+        // one more time to make sure we cycle back to the last view
+        assert!(repo.set_active_view_for_project(&proj1, &prev_view).is_ok());
+        let updated_proj1 = repo.get_project_by_id(proj1.id).unwrap().unwrap();
+        let prev_view = repo.get_prev_view_for_project(&updated_proj1).unwrap();
+        assert_eq!(prev_view, proj1_view0);
+    }
+
+    #[test]
+    fn test_get_prev_view_for_project_with_project_is_not_found() {
+        let conn = Connection::open_in_memory().unwrap();
+        let repo = Repository::new(conn).unwrap();
+
+        let project = repo.get_prev_view_for_project(&Project {
+            id: 1,
+            name: "proj1".to_string(),
+            active_view_id: 1,
+        });
+        assert!(project.is_err());
+    }
+
+    #[test]
+    fn test_get_window_manager_display_name_when_view_is_found() {
         let conn = Connection::open_in_memory().unwrap();
         let mut repo = Repository::new(conn).unwrap();
 
@@ -1117,6 +4370,28 @@ mod tests {
         assert!(view.is_err());
     }
 
+    #[test]
+    fn test_get_view_for_workspace() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let proj1 = repo.create_project("proj1").unwrap();
+        let view = repo.get_active_view_for_project(&proj1).unwrap();
+
+        assert_eq!(
+            repo.get_view_for_workspace("proj1#view0").unwrap(),
+            Some(view)
+        );
+        assert!(repo.get_view_for_workspace("proj1#gone").unwrap().is_none());
+        // windows on workspaces outside of muxwm belong to no view
+        assert!(repo.get_view_for_workspace("music").unwrap().is_none());
+        assert!(
+            repo.get_view_for_workspace("__i3_scratch")
+                .unwrap()
+                .is_none()
+        );
+    }
+
     #[test]
     fn test_upsert_pin_for_view_when_pin_not_used_and_view_is_found() {
         let conn = Connection::open_in_memory().unwrap();
@@ -1126,10 +4401,10 @@ mod tests {
         let view = repo.get_active_view_for_project(&proj1).unwrap();
 
         let key = "g";
-        assert!(repo.upsert_pin_for_view(key, &view).is_ok());
+        assert!(repo.upsert_pin_for_view(key, &view, false).is_ok());
 
         // make sure the pin was inserted
-        let pinned_view = repo.get_view_for_pin_key(key).unwrap().unwrap();
+        let pinned_view = repo.get_view_for_pin_key(key, None).unwrap().unwrap();
         assert_eq!(pinned_view.id, view.id);
     }
 
@@ -1145,7 +4420,10 @@ mod tests {
             position: 1,
         };
         let key = "g";
-        assert!(repo.upsert_pin_for_view(key, &non_existent_view).is_err());
+        assert!(
+            repo.upsert_pin_for_view(key, &non_existent_view, false)
+                .is_err()
+        );
     }
 
     #[test]
@@ -1158,22 +4436,38 @@ mod tests {
         // set initial pin
         let proj1 = repo.create_project("proj1").unwrap();
         let proj1_view = repo.get_active_view_for_project(&proj1).unwrap();
-        assert!(repo.upsert_pin_for_view(key, &proj1_view).is_ok());
+        assert!(repo.upsert_pin_for_view(key, &proj1_view, false).is_ok());
 
         // make sure the pin was inserted correctly
-        let pinned_view = repo.get_view_for_pin_key(key).unwrap().unwrap();
+        let pinned_view = repo.get_view_for_pin_key(key, None).unwrap().unwrap();
         assert_eq!(pinned_view.id, proj1_view.id);
 
-        // update the pin to the new view
+        // the key is only repointed with force
         let proj2 = repo.create_project("proj2").unwrap();
         let proj2_view = repo.get_active_view_for_project(&proj2).unwrap();
-        assert!(repo.upsert_pin_for_view(key, &proj2_view).is_ok());
+        assert!(repo.upsert_pin_for_view(key, &proj2_view, false).is_err());
+        let change = repo.upsert_pin_for_view(key, &proj2_view, true).unwrap();
+        assert_eq!(change.unwrap().replaced(), Some("proj1/view0"));
 
         // make sure the pin was updated
-        let pinned_view = repo.get_view_for_pin_key(key).unwrap().unwrap();
+        let pinned_view = repo.get_view_for_pin_key(key, None).unwrap().unwrap();
         assert_eq!(pinned_view.id, proj2_view.id);
     }
 
+    #[test]
+    fn test_upsert_pin_for_view_when_pin_already_points_to_the_view() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let proj1 = repo.create_project("proj1").unwrap();
+        let proj1_view = repo.get_active_view_for_project(&proj1).unwrap();
+        let change = repo.upsert_pin_for_view("g", &proj1_view, false).unwrap();
+        assert_eq!(change, Some(Default::default()));
+
+        let change = repo.upsert_pin_for_view("g", &proj1_view, false).unwrap();
+        assert_eq!(change, None);
+    }
+
     #[test]
     fn test_upsert_pin_for_view_when_pin_is_used_for_a_project() {
         let conn = Connection::open_in_memory().unwrap();
@@ -1183,15 +4477,17 @@ mod tests {
 
         // set initial pin
         let proj1 = repo.create_project("proj1").unwrap();
-        assert!(repo.upsert_pin_for_project(key, &proj1).is_ok());
+        assert!(repo.upsert_pin_for_project(key, &proj1, false).is_ok());
 
         // update the pin to the new view
         let proj2 = repo.create_project("proj2").unwrap();
         let proj2_view = repo.get_active_view_for_project(&proj2).unwrap();
-        assert!(repo.upsert_pin_for_view(key, &proj2_view).is_ok());
+        assert!(repo.upsert_pin_for_view(key, &proj2_view, false).is_err());
+        let change = repo.upsert_pin_for_view(key, &proj2_view, true).unwrap();
+        assert_eq!(change.unwrap().replaced(), Some("proj1"));
 
         // make sure the pin was updated
-        let pinned_view = repo.get_view_for_pin_key(key).unwrap().unwrap();
+        let pinned_view = repo.get_view_for_pin_key(key, None).unwrap().unwrap();
         assert_eq!(pinned_view.id, proj2_view.id);
     }
 
@@ -1203,10 +4499,16 @@ mod tests {
         // set initial pin
         let proj1 = repo.create_project("proj1").unwrap();
         let proj1_view = repo.get_active_view_for_project(&proj1).unwrap();
-        assert!(repo.upsert_pin_for_view("g", &proj1_view).is_ok());
-
-        // try to set a pin for the same view again
-        assert!(repo.upsert_pin_for_view("h", &proj1_view).is_err());
+        assert!(repo.upsert_pin_for_view("g", &proj1_view, false).is_ok());
+
+        // pinning the view again moves its pin to the new key
+        let change = repo.upsert_pin_for_view("h", &proj1_view, false).unwrap();
+        assert_eq!(change.unwrap().moved_from(), Some("g"));
+        assert!(repo.get_view_for_pin_key("g", None).unwrap().is_none());
+        assert_eq!(
+            repo.get_pin_key_for_view(&proj1_view).unwrap(),
+            Some("h".to_string())
+        );
     }
 
     #[test]
@@ -1216,11 +4518,11 @@ mod tests {
 
         let key = "g";
         let proj1 = repo.create_project("proj1").unwrap();
-        assert!(repo.upsert_pin_for_project(key, &proj1).is_ok());
+        assert!(repo.upsert_pin_for_project(key, &proj1, false).is_ok());
 
         // make sure the pin was inserted
         let proj1_active_view = repo.get_active_view_for_project(&proj1).unwrap();
-        let view = repo.get_view_for_pin_key(key).unwrap().unwrap();
+        let view = repo.get_view_for_pin_key(key, None).unwrap().unwrap();
         assert_eq!(view.id, proj1_active_view.id);
     }
 
@@ -1236,7 +4538,7 @@ mod tests {
         };
 
         let key = "g";
-        assert!(repo.upsert_pin_for_project(key, &proj1).is_err());
+        assert!(repo.upsert_pin_for_project(key, &proj1, false).is_err());
     }
 
     #[test]
@@ -1249,19 +4551,20 @@ mod tests {
         // set initial pin
         let proj1 = repo.create_project("proj1").unwrap();
         let proj1_view = repo.get_active_view_for_project(&proj1).unwrap();
-        assert!(repo.upsert_pin_for_view(key, &proj1_view).is_ok());
+        assert!(repo.upsert_pin_for_view(key, &proj1_view, false).is_ok());
 
         // make sure the pin was inserted correctly
-        let pinned_view = repo.get_view_for_pin_key(key).unwrap().unwrap();
+        let pinned_view = repo.get_view_for_pin_key(key, None).unwrap().unwrap();
         assert_eq!(pinned_view.id, proj1_view.id);
 
         // update the pin to the new view
         let proj2 = repo.create_project("proj2").unwrap();
         let proj2_view = repo.get_active_view_for_project(&proj2).unwrap();
-        assert!(repo.upsert_pin_for_project(key, &proj2).is_ok());
+        assert!(repo.upsert_pin_for_project(key, &proj2, false).is_err());
+        assert!(repo.upsert_pin_for_project(key, &proj2, true).is_ok());
 
         // make sure the pin was updated
-        let pinned_view = repo.get_view_for_pin_key(key).unwrap().unwrap();
+        let pinned_view = repo.get_view_for_pin_key(key, None).unwrap().unwrap();
         assert_eq!(pinned_view.id, proj2_view.id);
     }
 
@@ -1274,15 +4577,16 @@ mod tests {
 
         // set initial pin
         let proj1 = repo.create_project("proj1").unwrap();
-        assert!(repo.upsert_pin_for_project(key, &proj1).is_ok());
+        assert!(repo.upsert_pin_for_project(key, &proj1, false).is_ok());
 
         // update the pin to the new view
         let proj2 = repo.create_project("proj2").unwrap();
         let proj2_view = repo.get_active_view_for_project(&proj2).unwrap();
-        assert!(repo.upsert_pin_for_project(key, &proj2).is_ok());
+        assert!(repo.upsert_pin_for_project(key, &proj2, false).is_err());
+        assert!(repo.upsert_pin_for_project(key, &proj2, true).is_ok());
 
         // make sure the pin was updated
-        let pinned_view = repo.get_view_for_pin_key(key).unwrap().unwrap();
+        let pinned_view = repo.get_view_for_pin_key(key, None).unwrap().unwrap();
         assert_eq!(pinned_view.id, proj2_view.id);
     }
 
@@ -1293,10 +4597,16 @@ mod tests {
 
         // set initial pin
         let proj1 = repo.create_project("proj1").unwrap();
-        assert!(repo.upsert_pin_for_project("g", &proj1).is_ok());
-
-        // try to set a pin for the same project again
-        assert!(repo.upsert_pin_for_project("h", &proj1).is_err());
+        assert!(repo.upsert_pin_for_project("g", &proj1, false).is_ok());
+
+        // pinning the project again moves its pin to the new key
+        let change = repo.upsert_pin_for_project("h", &proj1, false).unwrap();
+        assert_eq!(change.unwrap().moved_from(), Some("g"));
+        assert!(repo.get_view_for_pin_key("g", None).unwrap().is_none());
+        assert_eq!(
+            repo.get_pin_key_for_project(&proj1).unwrap(),
+            Some("h".to_string())
+        );
     }
 
     #[test]
@@ -1311,16 +4621,22 @@ mod tests {
         let proj2 = repo.create_project("proj2").unwrap();
 
         let proj1_key = "g";
-        assert!(repo.upsert_pin_for_view(proj1_key, &proj1_view).is_ok());
+        assert!(
+            repo.upsert_pin_for_view(proj1_key, &proj1_view, false)
+                .is_ok()
+        );
 
         let proj2_key = "h";
-        assert!(repo.upsert_pin_for_project(proj2_key, &proj2).is_ok());
+        assert!(
+            repo.upsert_pin_for_project(proj2_key, &proj2, false)
+                .is_ok()
+        );
 
         let pins = repo.list_pins().unwrap();
         assert_eq!(pins.len(), 2);
-        assert_eq!(pins[0]._id, proj1_view_id);
+        assert_eq!(pins[0].id, proj1_view_id);
         assert_eq!(pins[0].pin_type, PinType::View);
-        assert_eq!(pins[1]._id, proj2.id);
+        assert_eq!(pins[1].id, proj2.id);
         assert_eq!(pins[1].pin_type, PinType::Project);
     }
 
@@ -1344,13 +4660,13 @@ mod tests {
         // set pin
         let proj1 = repo.create_project("proj1").unwrap();
         let proj1_view = repo.get_active_view_for_project(&proj1).unwrap();
-        assert!(repo.upsert_pin_for_view(key, &proj1_view).is_ok());
+        assert!(repo.upsert_pin_for_view(key, &proj1_view, false).is_ok());
 
         // clear the pin
         assert!(repo.clear_pin(key).is_ok());
 
         // make sure the pin was cleared
-        let pinned_view = repo.get_view_for_pin_key(key).unwrap();
+        let pinned_view = repo.get_view_for_pin_key(key, None).unwrap();
         assert!(pinned_view.is_none());
     }
 
@@ -1371,9 +4687,9 @@ mod tests {
         let pin_key = "g";
         let project = repo.create_project("proj1").unwrap();
         let view = repo.get_active_view_for_project(&project).unwrap();
-        repo.upsert_pin_for_view(pin_key, &view).unwrap();
+        repo.upsert_pin_for_view(pin_key, &view, false).unwrap();
 
-        let retrieved_view = repo.get_view_for_pin_key(pin_key).unwrap().unwrap();
+        let retrieved_view = repo.get_view_for_pin_key(pin_key, None).unwrap().unwrap();
         assert_eq!(retrieved_view, view);
     }
 
@@ -1384,9 +4700,10 @@ mod tests {
 
         let pin_key = "g";
         let project = repo.create_project("proj1").unwrap();
-        repo.upsert_pin_for_project(pin_key, &project).unwrap();
+        repo.upsert_pin_for_project(pin_key, &project, false)
+            .unwrap();
 
-        let retrieved_view = repo.get_view_for_pin_key(pin_key).unwrap().unwrap();
+        let retrieved_view = repo.get_view_for_pin_key(pin_key, None).unwrap().unwrap();
         assert_eq!(retrieved_view.id, project.active_view_id);
     }
 
@@ -1395,7 +4712,7 @@ mod tests {
         let conn = Connection::open_in_memory().unwrap();
         let repo = Repository::new(conn).unwrap();
 
-        let view = repo.get_view_for_pin_key("g").unwrap();
+        let view = repo.get_view_for_pin_key("g", None).unwrap();
         assert!(view.is_none());
     }
 
@@ -1410,10 +4727,13 @@ mod tests {
 
         // set the project pin
         let proj1_key = "g";
-        assert!(repo.upsert_pin_for_project(proj1_key, &proj1).is_ok());
+        assert!(
+            repo.upsert_pin_for_project(proj1_key, &proj1, false)
+                .is_ok()
+        );
 
         // make sure the active view is returned
-        let view = repo.get_view_for_pin_key(proj1_key).unwrap().unwrap();
+        let view = repo.get_view_for_pin_key(proj1_key, None).unwrap().unwrap();
         assert_eq!(view.id, proj1_view0.id);
 
         // now update the active view
@@ -1421,7 +4741,7 @@ mod tests {
             .unwrap();
 
         // now get the pin again and make sure the updated view is returned
-        let view = repo.get_view_for_pin_key(proj1_key).unwrap().unwrap();
+        let view = repo.get_view_for_pin_key(proj1_key, None).unwrap().unwrap();
         assert_eq!(view.id, proj1_view1.id);
     }
 
@@ -1430,60 +4750,866 @@ mod tests {
         let conn = Connection::open_in_memory().unwrap();
         let repo = Repository::new(conn).unwrap();
 
-        let view = repo.get_view_for_pin_key("g").unwrap();
+        let view = repo.get_view_for_pin_key("g", None).unwrap();
         assert!(view.is_none());
     }
 
     #[test]
-    fn test_get_pin_key_for_view_when_pin_is_found() {
+    fn test_get_view_for_pin_key_resolves_local_pin_in_current_project() {
         let conn = Connection::open_in_memory().unwrap();
         let mut repo = Repository::new(conn).unwrap();
 
-        let pin_key = "g";
-        let project = repo.create_project("proj1").unwrap();
-        let view = repo.get_active_view_for_project(&project).unwrap();
-        repo.upsert_pin_for_view(pin_key, &view).unwrap();
+        let proj1 = repo.create_project("proj1").unwrap();
+        let proj1_view1 = repo.create_view_in_project(&proj1, "view1").unwrap();
+        let proj2 = repo.create_project("proj2").unwrap();
+        let proj2_view1 = repo.create_view_in_project(&proj2, "view1").unwrap();
 
-        let retrieved_pin_key = repo.get_pin_key_for_view(&view).unwrap().unwrap();
-        assert_eq!(retrieved_pin_key, pin_key);
+        // the same key in two projects
+        repo.upsert_local_pin_for_view("1", &proj1_view1, false)
+            .unwrap();
+        repo.upsert_local_pin_for_view("1", &proj2_view1, false)
+            .unwrap();
+
+        let view = repo.get_view_for_pin_key("1", Some(&proj1_view1)).unwrap();
+        assert_eq!(view, Some(proj1_view1));
+        let view = repo.get_view_for_pin_key("1", Some(&proj2_view1)).unwrap();
+        assert_eq!(view, Some(proj2_view1));
+
+        // local pins are not visible outside of a project
+        let view = repo.get_view_for_pin_key("1", None).unwrap();
+        assert!(view.is_none());
     }
 
     #[test]
-    fn test_get_pin_key_for_view_when_pin_is_not_found() {
+    fn test_get_view_for_pin_key_local_pin_shadows_global_pin() {
         let conn = Connection::open_in_memory().unwrap();
-        let repo = Repository::new(conn).unwrap();
+        let mut repo = Repository::new(conn).unwrap();
 
-        let view = View {
-            id: 1,
-            name: "view1".to_string(),
-            project_id: 1,
-            position: 1,
-        };
+        let proj1 = repo.create_project("proj1").unwrap();
+        let proj1_view0 = repo.get_active_view_for_project(&proj1).unwrap();
+        let proj1_view1 = repo.create_view_in_project(&proj1, "view1").unwrap();
+        let proj2 = repo.create_project("proj2").unwrap();
+        let proj2_view0 = repo.get_active_view_for_project(&proj2).unwrap();
 
-        let pin_key = repo.get_pin_key_for_view(&view).unwrap();
-        assert!(pin_key.is_none());
+        repo.upsert_pin_for_view("1", &proj1_view0, false).unwrap();
+        repo.upsert_local_pin_for_view("1", &proj1_view1, false)
+            .unwrap();
+
+        let view = repo.get_view_for_pin_key("1", Some(&proj1_view0)).unwrap();
+        assert_eq!(view, Some(proj1_view1));
+
+        // other projects fall back to the global pin
+        let view = repo.get_view_for_pin_key("1", Some(&proj2_view0)).unwrap();
+        assert_eq!(view, Some(proj1_view0));
     }
 
     #[test]
-    fn test_get_pin_key_for_project_when_pin_is_found() {
+    fn test_upsert_local_pin_for_view_when_key_is_used_in_the_project() {
         let conn = Connection::open_in_memory().unwrap();
         let mut repo = Repository::new(conn).unwrap();
 
-        let pin_key = "g";
-        let project = repo.create_project("proj1").unwrap();
-        repo.upsert_pin_for_project(pin_key, &project).unwrap();
+        let proj1 = repo.create_project("proj1").unwrap();
+        let proj1_view0 = repo.get_active_view_for_project(&proj1).unwrap();
+        let proj1_view1 = repo.create_view_in_project(&proj1, "view1").unwrap();
 
-        let retrieved_pin_key = repo.get_pin_key_for_project(&project).unwrap().unwrap();
-        assert_eq!(retrieved_pin_key, pin_key);
+        repo.upsert_local_pin_for_view("1", &proj1_view0, false)
+            .unwrap();
+        assert!(
+            repo.upsert_local_pin_for_view("1", &proj1_view1, false)
+                .is_err()
+        );
+        repo.upsert_local_pin_for_view("1", &proj1_view1, true)
+            .unwrap();
+
+        let view = repo.get_view_for_pin_key("1", Some(&proj1_view0)).unwrap();
+        assert_eq!(view, Some(proj1_view1));
+        assert_eq!(repo.list_pins().unwrap().len(), 1);
     }
 
     #[test]
-    fn test_get_pin_key_for_project_when_pin_is_not_found() {
+    fn test_upsert_local_pin_for_view_when_view_is_already_pinned() {
         let conn = Connection::open_in_memory().unwrap();
         let mut repo = Repository::new(conn).unwrap();
 
-        let project = repo.create_project("proj1").unwrap();
-        let pin_key = repo.get_pin_key_for_project(&project).unwrap();
-        assert!(pin_key.is_none());
+        let proj1 = repo.create_project("proj1").unwrap();
+        let proj1_view0 = repo.get_active_view_for_project(&proj1).unwrap();
+        repo.upsert_local_pin_for_view("1", &proj1_view0, false)
+            .unwrap();
+
+        let change = repo
+            .upsert_local_pin_for_view("2", &proj1_view0, false)
+            .unwrap();
+        assert_eq!(change.unwrap().moved_from(), Some("1"));
+        assert!(
+            repo.get_view_for_pin_key("1", Some(&proj1_view0))
+                .unwrap()
+                .is_none()
+        );
+        assert_eq!(repo.list_pins().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_rename_pin() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let proj1 = repo.create_project("proj1").unwrap();
+        let proj1_view0 = repo.get_active_view_for_project(&proj1).unwrap();
+        repo.upsert_pin_for_view("a", &proj1_view0, false).unwrap();
+        repo.upsert_pin_for_project("b", &proj1, false).unwrap();
+
+        assert!(repo.rename_pin("a", "b").is_err());
+        assert!(repo.rename_pin("missing", "c").is_err());
+        repo.rename_pin("a", "c").unwrap();
+
+        assert!(repo.get_view_for_pin_key("a", None).unwrap().is_none());
+        assert_eq!(
+            repo.get_pin_key_for_view(&proj1_view0).unwrap(),
+            Some("c".to_string())
+        );
+    }
+
+    #[test]
+    fn test_swap_pins() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let proj1 = repo.create_project("proj1").unwrap();
+        let proj1_view0 = repo.get_active_view_for_project(&proj1).unwrap();
+        let proj2 = repo.create_project("proj2").unwrap();
+        repo.upsert_pin_for_view("a", &proj1_view0, false).unwrap();
+        repo.upsert_pin_for_project("b", &proj2, false).unwrap();
+
+        assert!(repo.swap_pins("a", "missing").is_err());
+        repo.swap_pins("a", "b").unwrap();
+
+        assert_eq!(
+            repo.get_pin_key_for_view(&proj1_view0).unwrap(),
+            Some("b".to_string())
+        );
+        assert_eq!(
+            repo.get_pin_key_for_project(&proj2).unwrap(),
+            Some("a".to_string())
+        );
+
+        // a swap is a single journal entry
+        repo.undo().unwrap().unwrap();
+        assert_eq!(
+            repo.get_pin_key_for_view(&proj1_view0).unwrap(),
+            Some("a".to_string())
+        );
+    }
+
+    #[test]
+    fn test_get_view_for_pin_key_cycles_through_a_stack() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let proj1 = repo.create_project("proj1").unwrap();
+        let proj1_view0 = repo.get_active_view_for_project(&proj1).unwrap();
+        let proj1_view1 = repo.create_view_in_project(&proj1, "view1").unwrap();
+        let proj2 = repo.create_project("proj2").unwrap();
+        let proj2_view0 = repo.get_active_view_for_project(&proj2).unwrap();
+        let proj3 = repo.create_project("proj3").unwrap();
+        let proj3_view0 = repo.get_active_view_for_project(&proj3).unwrap();
+        repo.add_view_to_pin_stack("t", &proj1_view1).unwrap();
+        repo.add_view_to_pin_stack("t", &proj2_view0).unwrap();
+        assert_eq!(
+            repo.get_view_for_pin(&repo.get_pin_by_key("t").unwrap().unwrap())
+                .unwrap(),
+            Some(proj1_view1.clone())
+        );
+
+        // what `pin focus` does: resolve the key from the focused view, then
+        // focus what it resolved to
+        let focus = |repo: &mut Repository, focused: Option<&View>| {
+            let Some(PinTarget::View(view)) = repo.get_pin_target_for_key("t", focused).unwrap()
+            else {
+                panic!("expected the stack to resolve to a view");
+            };
+            repo.mark_view_focused(&view).unwrap();
+            view
+        };
+
+        // from an unmanaged workspace, the stack starts at its first view and
+        // cycles from there, wrapping around
+        let mut focused = None;
+        for expected in [&proj1_view1, &proj2_view0, &proj1_view1, &proj2_view0] {
+            let view = focus(&mut repo, focused.as_ref());
+            assert_eq!(&view, expected);
+            focused = Some(view);
+        }
+
+        // from a view outside of the stack, it starts over, even when that
+        // view shares a project with a view of the stack
+        for outside in [&proj3_view0, &proj1_view0] {
+            let view = focus(&mut repo, Some(outside));
+            assert_eq!(view, proj1_view1);
+            let view = focus(&mut repo, Some(&view));
+            assert_eq!(view, proj2_view0);
+        }
+
+        // a local pin of the focused view's project still takes precedence
+        repo.upsert_local_pin_for_view("t", &proj1_view0, false)
+            .unwrap();
+        let view = focus(&mut repo, Some(&proj1_view1));
+        assert_eq!(view, proj1_view0);
+        let view = focus(&mut repo, Some(&proj2_view0));
+        assert_eq!(view, proj1_view1);
+    }
+
+    #[test]
+    fn test_add_view_to_pin_stack_when_key_is_not_a_stack() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let proj1 = repo.create_project("proj1").unwrap();
+        let proj1_view0 = repo.get_active_view_for_project(&proj1).unwrap();
+        repo.upsert_pin_for_project("p", &proj1, false).unwrap();
+
+        assert!(repo.add_view_to_pin_stack("p", &proj1_view0).is_err());
+        repo.add_view_to_pin_stack("t", &proj1_view0).unwrap();
+        assert!(repo.add_view_to_pin_stack("t", &proj1_view0).is_err());
+        assert_eq!(repo.list_pins().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_remove_view_from_pin_stack() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let proj1 = repo.create_project("proj1").unwrap();
+        let proj1_view0 = repo.get_active_view_for_project(&proj1).unwrap();
+        let proj1_view1 = repo.create_view_in_project(&proj1, "view1").unwrap();
+        repo.add_view_to_pin_stack("t", &proj1_view0).unwrap();
+        repo.add_view_to_pin_stack("t", &proj1_view1).unwrap();
+
+        assert!(
+            repo.remove_view_from_pin_stack("missing", &proj1_view0)
+                .is_err()
+        );
+        repo.remove_view_from_pin_stack("t", &proj1_view0).unwrap();
+        assert!(repo.remove_view_from_pin_stack("t", &proj1_view0).is_err());
+        let pin = repo.get_pin_by_key("t").unwrap().unwrap();
+        assert_eq!(
+            repo.list_pin_stack_views(&pin).unwrap(),
+            vec![proj1_view1.clone()]
+        );
+
+        // the stack goes away with its last view, and comes back with undo
+        repo.remove_view_from_pin_stack("t", &proj1_view1).unwrap();
+        assert!(repo.list_pins().unwrap().is_empty());
+        repo.undo().unwrap().unwrap();
+        let view = repo.get_view_for_pin_key("t", None).unwrap().unwrap();
+        assert_eq!(view, proj1_view1);
+    }
+
+    #[test]
+    fn test_upsert_pin_for_view_when_key_is_a_stack() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let proj1 = repo.create_project("proj1").unwrap();
+        let proj1_view0 = repo.get_active_view_for_project(&proj1).unwrap();
+        let proj1_view1 = repo.create_view_in_project(&proj1, "view1").unwrap();
+        repo.add_view_to_pin_stack("t", &proj1_view0).unwrap();
+
+        assert!(repo.upsert_pin_for_view("t", &proj1_view1, false).is_err());
+        let change = repo
+            .upsert_pin_for_view("t", &proj1_view1, true)
+            .unwrap()
+            .unwrap();
+        assert_eq!(change.replaced(), Some("a stack of 1 view(s)"));
+        let pin = repo.get_pin_by_key("t").unwrap().unwrap();
+        assert_eq!(pin.pin_type(), PinType::View);
+        assert!(repo.list_pin_stack_views(&pin).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_upsert_external_pin() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let proj1 = repo.create_project("proj1").unwrap();
+        repo.upsert_pin_for_project("p", &proj1, false).unwrap();
+
+        assert!(
+            repo.upsert_external_pin("w", PinType::View, "music", false)
+                .is_err()
+        );
+        assert!(
+            repo.upsert_external_pin("w", PinType::Window, "firefox", false)
+                .is_err()
+        );
+        // managed workspaces are pinned through their views
+        assert!(
+            repo.upsert_external_pin("w", PinType::Workspace, "proj1#view0", true)
+                .is_err()
+        );
+        assert!(
+            repo.upsert_external_pin("p", PinType::Workspace, "music", false)
+                .is_err()
+        );
+
+        let change = repo
+            .upsert_external_pin("p", PinType::Workspace, "music", true)
+            .unwrap()
+            .unwrap();
+        assert_eq!(change.replaced(), Some("proj1"));
+        assert!(
+            repo.upsert_external_pin("p", PinType::Workspace, "music", false)
+                .unwrap()
+                .is_none()
+        );
+
+        // pinning the workspace again moves its key
+        let change = repo
+            .upsert_external_pin("m", PinType::Workspace, "music", false)
+            .unwrap()
+            .unwrap();
+        assert_eq!(change.moved_from(), Some("p"));
+        let pins = repo.list_pins().unwrap();
+        assert_eq!(pins.len(), 1);
+        assert_eq!(pins[0].key(), "m");
+        assert_eq!(pins[0].target(), Some("music"));
+    }
+
+    #[test]
+    fn test_get_pin_target_for_key() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let proj1 = repo.create_project("proj1").unwrap();
+        let proj1_view0 = repo.get_active_view_for_project(&proj1).unwrap();
+        repo.upsert_pin_for_view("v", &proj1_view0, false).unwrap();
+        repo.upsert_external_pin("m", PinType::Workspace, "music", false)
+            .unwrap();
+        repo.upsert_external_pin("w", PinType::Window, "94", false)
+            .unwrap();
+        repo.upsert_external_pin("b", PinType::Mark, "browser", false)
+            .unwrap();
+
+        assert_eq!(
+            repo.get_pin_target_for_key("v", None).unwrap(),
+            Some(PinTarget::View(proj1_view0.clone()))
+        );
+        assert_eq!(
+            repo.get_pin_target_for_key("m", None).unwrap(),
+            Some(PinTarget::Workspace("music".to_string()))
+        );
+        assert_eq!(
+            repo.get_pin_target_for_key("w", None).unwrap(),
+            Some(PinTarget::Window(94))
+        );
+        assert_eq!(
+            repo.get_pin_target_for_key("b", None).unwrap(),
+            Some(PinTarget::Mark("browser".to_string()))
+        );
+        assert_eq!(repo.get_pin_target_for_key("x", None).unwrap(), None);
+        assert!(repo.get_view_for_pin_key("m", None).unwrap().is_none());
+
+        // a local pin still takes precedence
+        repo.upsert_local_pin_for_view("m", &proj1_view0, false)
+            .unwrap();
+        assert_eq!(
+            repo.get_pin_target_for_key("m", Some(&proj1_view0))
+                .unwrap(),
+            Some(PinTarget::View(proj1_view0))
+        );
+    }
+
+    #[test]
+    fn test_clear_local_pin_keeps_other_pins_with_the_same_key() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let proj1 = repo.create_project("proj1").unwrap();
+        let proj1_view0 = repo.get_active_view_for_project(&proj1).unwrap();
+        let proj2 = repo.create_project("proj2").unwrap();
+        let proj2_view0 = repo.get_active_view_for_project(&proj2).unwrap();
+
+        repo.upsert_local_pin_for_view("1", &proj1_view0, false)
+            .unwrap();
+        repo.upsert_local_pin_for_view("1", &proj2_view0, false)
+            .unwrap();
+        repo.upsert_pin_for_view("1", &proj2_view0, false).unwrap();
+
+        repo.clear_local_pin(&proj1, "1").unwrap();
+
+        let pins = repo.list_pins().unwrap();
+        assert_eq!(pins.len(), 2);
+        assert!(repo.get_view_for_pin_key("1", None).unwrap().is_some());
+
+        // clearing the global pin leaves the local pins alone
+        repo.clear_pin("1").unwrap();
+        let pins = repo.list_pins().unwrap();
+        assert_eq!(pins.len(), 1);
+        assert_eq!(pins[0].pin_type, PinType::Local);
+    }
+
+    #[test]
+    fn test_get_view_for_pin() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let proj1 = repo.create_project("proj1").unwrap();
+        let proj1_view0 = repo.get_active_view_for_project(&proj1).unwrap();
+        let proj1_view1 = repo.create_view_in_project(&proj1, "view1").unwrap();
+        let proj2 = repo.create_project("proj2").unwrap();
+        let proj2_view0 = repo.get_active_view_for_project(&proj2).unwrap();
+
+        repo.upsert_pin_for_project("p", &proj1, false).unwrap();
+        repo.upsert_pin_for_view("v", &proj2_view0, false).unwrap();
+        repo.upsert_local_pin_for_view("1", &proj1_view1, false)
+            .unwrap();
+
+        let views = repo
+            .list_pins()
+            .unwrap()
+            .iter()
+            .map(|pin| repo.get_view_for_pin(pin).unwrap().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(views, vec![proj1_view0, proj2_view0, proj1_view1]);
+    }
+
+    #[test]
+    fn test_pin_is_in_scope() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let proj1 = repo.create_project("proj1").unwrap();
+        let proj1_view0 = repo.get_active_view_for_project(&proj1).unwrap();
+        let proj2 = repo.create_project("proj2").unwrap();
+
+        repo.upsert_pin_for_project("g", &proj2, false).unwrap();
+        repo.upsert_local_pin_for_view("1", &proj1_view0, false)
+            .unwrap();
+
+        let pins = repo.list_pins().unwrap();
+        assert!(pins[0].is_in_scope(None));
+        assert!(!pins[1].is_in_scope(None));
+        assert!(pins[1].is_in_scope(Some(&proj1)));
+        assert!(!pins[1].is_in_scope(Some(&proj2)));
+    }
+
+    #[test]
+    fn test_get_pin_key_for_view_when_pin_is_found() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let pin_key = "g";
+        let project = repo.create_project("proj1").unwrap();
+        let view = repo.get_active_view_for_project(&project).unwrap();
+        repo.upsert_pin_for_view(pin_key, &view, false).unwrap();
+
+        let retrieved_pin_key = repo.get_pin_key_for_view(&view).unwrap().unwrap();
+        assert_eq!(retrieved_pin_key, pin_key);
+    }
+
+    #[test]
+    fn test_get_pin_key_for_view_when_pin_is_not_found() {
+        let conn = Connection::open_in_memory().unwrap();
+        let repo = Repository::new(conn).unwrap();
+
+        let view = View {
+            id: 1,
+            name: "view1".to_string(),
+            project_id: 1,
+            position: 1,
+        };
+
+        let pin_key = repo.get_pin_key_for_view(&view).unwrap();
+        assert!(pin_key.is_none());
+    }
+
+    #[test]
+    fn test_get_pin_key_for_project_when_pin_is_found() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let pin_key = "g";
+        let project = repo.create_project("proj1").unwrap();
+        repo.upsert_pin_for_project(pin_key, &project, false)
+            .unwrap();
+
+        let retrieved_pin_key = repo.get_pin_key_for_project(&project).unwrap().unwrap();
+        assert_eq!(retrieved_pin_key, pin_key);
+    }
+
+    #[test]
+    fn test_get_pin_key_for_project_when_pin_is_not_found() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let project = repo.create_project("proj1").unwrap();
+        let pin_key = repo.get_pin_key_for_project(&project).unwrap();
+        assert!(pin_key.is_none());
+    }
+
+    fn repo_with_state() -> Repository {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let proj1 = repo.create_project("proj1").unwrap();
+        let proj1_view0 = repo.get_active_view_for_project(&proj1).unwrap();
+        let proj1_view1 = repo.create_view_in_project(&proj1, "view1").unwrap();
+        repo.set_active_view_for_project(&proj1, &proj1_view1)
+            .unwrap();
+        repo.set_project_root(&proj1, Some("/src/proj1")).unwrap();
+        repo.set_project_env(&proj1, "A", "1").unwrap();
+        repo.add_view_command(&proj1_view0, "alacritty").unwrap();
+        repo.set_view_layout(&proj1_view1, Some("{}")).unwrap();
+        repo.upsert_pin_for_project("p", &proj1, false).unwrap();
+        repo.upsert_pin_for_view("v", &proj1_view1, false).unwrap();
+        repo.upsert_local_pin_for_view("1", &proj1_view0, false)
+            .unwrap();
+        repo.add_view_to_pin_stack("t", &proj1_view1).unwrap();
+        repo.add_view_to_pin_stack("t", &proj1_view0).unwrap();
+        repo.upsert_external_pin("m", PinType::Workspace, "music", false)
+            .unwrap();
+
+        repo.create_project("proj2").unwrap();
+        repo
+    }
+
+    #[test]
+    fn test_export_state() {
+        let repo = repo_with_state();
+
+        let state = repo.export_state().unwrap();
+        assert_eq!(state.projects.len(), 2);
+        let proj1 = &state.projects[0];
+        assert_eq!(proj1.name, "proj1");
+        assert_eq!(proj1.root.as_deref(), Some("/src/proj1"));
+        assert_eq!(proj1.env.get("A").map(String::as_str), Some("1"));
+        assert_eq!(proj1.views.len(), 2);
+        assert_eq!(proj1.views[0].commands, vec!["alacritty".to_string()]);
+        assert!(!proj1.views[0].active);
+        assert!(proj1.views[1].active);
+        assert_eq!(proj1.views[1].layout.as_deref(), Some("{}"));
+
+        let pins: Vec<_> = state
+            .pins
+            .iter()
+            .map(|p| {
+                (
+                    p.key.as_str(),
+                    p.pin_type,
+                    p.project.as_str(),
+                    p.view.as_deref(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            pins,
+            vec![
+                ("p", PinType::Project, "proj1", None),
+                ("v", PinType::View, "proj1", Some("view1")),
+                ("1", PinType::Local, "proj1", Some("view0")),
+            ]
+        );
+
+        assert_eq!(state.stacks.len(), 1);
+        assert_eq!(state.stacks[0].key, "t");
+        let stack_views: Vec<_> = state.stacks[0]
+            .views
+            .iter()
+            .map(|v| (v.project.as_str(), v.view.as_str()))
+            .collect();
+        assert_eq!(stack_views, vec![("proj1", "view1"), ("proj1", "view0")]);
+
+        assert_eq!(state.external_pins.len(), 1);
+        assert_eq!(state.external_pins[0].pin_type, PinType::Workspace);
+        assert_eq!(state.external_pins[0].target, "music");
+    }
+
+    #[test]
+    fn test_import_state_into_empty_repository() {
+        let state = repo_with_state().export_state().unwrap();
+
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+        repo.import_state(&state, ImportMode::Merge).unwrap();
+
+        assert_eq!(repo.export_state().unwrap(), state);
+        let proj1 = repo.get_project_by_name("proj1").unwrap().unwrap();
+        let proj1_view1 = repo.get_active_view_for_project(&proj1).unwrap();
+        let view = repo
+            .get_view_for_pin_key("1", Some(&proj1_view1))
+            .unwrap()
+            .unwrap();
+        assert_eq!(view.name(), "view0");
+    }
+
+    #[test]
+    fn test_import_state_when_merging_duplicates() {
+        let mut repo = repo_with_state();
+        let state = repo.export_state().unwrap();
+
+        let conflicts = repo.import_conflicts(&state, ImportMode::Merge).unwrap();
+        assert_eq!(
+            conflicts,
+            vec![
+                "project 'proj1' already exists".to_string(),
+                "project 'proj2' already exists".to_string(),
+                "pin key 'p' is already in use".to_string(),
+                "pin key 'v' is already in use".to_string(),
+                "pin key 't' is already in use".to_string(),
+                "pin key 'm' is already in use".to_string(),
+            ]
+        );
+        assert!(repo.import_state(&state, ImportMode::Merge).is_err());
+        assert_eq!(repo.list_projects(SortOrder::Created).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_import_state_when_replacing() {
+        let mut repo = repo_with_state();
+        let mut state = repo.export_state().unwrap();
+        state.projects.remove(1);
+
+        assert!(
+            repo.import_conflicts(&state, ImportMode::Replace)
+                .unwrap()
+                .is_empty()
+        );
+        repo.import_state(&state, ImportMode::Replace).unwrap();
+
+        assert_eq!(repo.export_state().unwrap(), state);
+        assert!(repo.get_project_by_name("proj2").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_delete_view() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let proj1 = repo.create_project("proj1").unwrap();
+        let proj1_view1 = repo.create_view_in_project(&proj1, "view1").unwrap();
+        repo.upsert_pin_for_view("v", &proj1_view1, false).unwrap();
+        repo.add_view_command(&proj1_view1, "alacritty").unwrap();
+        repo.mark_view_focused_at(&proj1_view1, 100).unwrap();
+        repo.mark_view_focused_at(&repo.get_active_view_for_project(&proj1).unwrap(), 200)
+            .unwrap();
+
+        repo.add_view_to_pin_stack("t", &proj1_view1).unwrap();
+
+        repo.delete_view(&proj1_view1).unwrap();
+
+        assert!(repo.get_view_by_id(proj1_view1.id).unwrap().is_none());
+        assert!(repo.list_pins().unwrap().is_empty());
+        assert!(repo.list_view_commands(&proj1_view1).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_delete_view_when_view_is_active() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let proj1 = repo.create_project("proj1").unwrap();
+        let proj1_view0 = repo.get_active_view_for_project(&proj1).unwrap();
+
+        assert!(repo.delete_view(&proj1_view0).is_err());
+        assert!(repo.get_view_by_id(proj1_view0.id).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_reorder_views() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let proj1 = repo.create_project("proj1").unwrap();
+        let proj1_view0 = repo.get_active_view_for_project(&proj1).unwrap();
+        let proj1_view1 = repo.create_view_in_project(&proj1, "view1").unwrap();
+        let proj1_view2 = repo.create_view_in_project(&proj1, "view2").unwrap();
+
+        repo.reorder_views(
+            &proj1,
+            &[
+                proj1_view2.clone(),
+                proj1_view0.clone(),
+                proj1_view1.clone(),
+            ],
+        )
+        .unwrap();
+
+        let names: Vec<_> = repo
+            .list_views_for_project(&proj1, SortOrder::Position)
+            .unwrap()
+            .into_iter()
+            .map(|v| v.name)
+            .collect();
+        assert_eq!(names, vec!["view2", "view0", "view1"]);
+    }
+
+    #[test]
+    fn test_reorder_views_when_views_are_missing() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let proj1 = repo.create_project("proj1").unwrap();
+        let proj1_view0 = repo.get_active_view_for_project(&proj1).unwrap();
+        repo.create_view_in_project(&proj1, "view1").unwrap();
+        let proj2 = repo.create_project("proj2").unwrap();
+        let proj2_view0 = repo.get_active_view_for_project(&proj2).unwrap();
+
+        assert!(
+            repo.reorder_views(&proj1, std::slice::from_ref(&proj1_view0))
+                .is_err()
+        );
+        assert!(
+            repo.reorder_views(&proj1, &[proj1_view0.clone(), proj1_view0.clone()])
+                .is_err()
+        );
+        assert!(
+            repo.reorder_views(&proj1, &[proj1_view0, proj2_view0])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_undo_when_journal_is_empty() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        assert!(repo.undo().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_undo_rename_view() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let proj1 = repo.create_project("proj1").unwrap();
+        let view = repo.get_active_view_for_project(&proj1).unwrap();
+        repo.rename_view(&view, "code").unwrap();
+
+        let undo = repo.undo().unwrap().unwrap();
+        assert_eq!(undo.description(), "rename view 'proj1#view0' to 'code'");
+        assert_eq!(
+            undo.workspace_renames(),
+            &[("proj1#code".to_string(), "proj1#view0".to_string())]
+        );
+        assert_eq!(
+            repo.get_view_by_id(view.id).unwrap().unwrap().name(),
+            "view0"
+        );
+    }
+
+    #[test]
+    fn test_undo_pin_overwrite_restores_previous_pin() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let proj1 = repo.create_project("proj1").unwrap();
+        let proj1_view0 = repo.get_active_view_for_project(&proj1).unwrap();
+        let proj1_view1 = repo.create_view_in_project(&proj1, "view1").unwrap();
+        repo.upsert_pin_for_view("a", &proj1_view0, false).unwrap();
+        repo.upsert_pin_for_view("a", &proj1_view1, true).unwrap();
+
+        repo.undo().unwrap().unwrap();
+        let view = repo.get_view_for_pin_key("a", None).unwrap().unwrap();
+        assert_eq!(view.name(), "view0");
+
+        repo.undo().unwrap().unwrap();
+        assert!(repo.get_view_for_pin_key("a", None).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_undo_delete_project_restores_everything() {
+        let mut repo = repo_with_state();
+        let before = repo.export_state().unwrap();
+
+        let proj1 = repo.get_project_by_name("proj1").unwrap().unwrap();
+        repo.delete_project(&proj1).unwrap();
+        repo.undo().unwrap().unwrap();
+
+        assert_eq!(repo.export_state().unwrap(), before);
+    }
+
+    #[test]
+    fn test_undo_create_project_after_focusing_it() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let proj1 = repo.create_project("proj1").unwrap();
+        let view = repo.create_view_in_project(&proj1, "view1").unwrap();
+        repo.mark_view_focused_at(&view, 100).unwrap();
+
+        assert_eq!(
+            repo.undo().unwrap().unwrap().description(),
+            "create view 'view1' in project 'proj1'"
+        );
+        assert!(repo.get_view_by_id(view.id).unwrap().is_none());
+        repo.undo().unwrap().unwrap();
+        assert!(repo.get_project_by_name("proj1").unwrap().is_none());
+        assert!(repo.undo().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_undo_create_view_after_switching_to_it() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let proj1 = repo.create_project("proj1").unwrap();
+        let view0 = repo.get_active_view_for_project(&proj1).unwrap();
+        let view1 = repo.create_view_in_project(&proj1, "view1").unwrap();
+        repo.set_active_view_for_project(&proj1, &view1).unwrap();
+        repo.set_project_root(&proj1, Some("/src/proj1")).unwrap();
+
+        // switching views is not journaled, nor undone with the root
+        repo.undo().unwrap().unwrap();
+        let proj1 = repo.get_project_by_id(proj1.id).unwrap().unwrap();
+        assert_eq!(repo.get_active_view_for_project(&proj1).unwrap(), view1);
+
+        assert_eq!(
+            repo.undo().unwrap().unwrap().description(),
+            "create view 'view1' in project 'proj1'"
+        );
+        let proj1 = repo.get_project_by_id(proj1.id).unwrap().unwrap();
+        assert_eq!(repo.get_active_view_for_project(&proj1).unwrap(), view0);
+    }
+
+    #[test]
+    fn test_failed_batch_changes_nothing() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let result: anyhow::Result<()> = repo.batch("create projects".to_string(), |repo| {
+            repo.create_project("proj1")?;
+            repo.create_project("proj1")?;
+            Ok(())
+        });
+
+        assert!(result.is_err());
+        assert!(repo.get_project_by_name("proj1").unwrap().is_none());
+        assert!(repo.list_journal(10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_list_journal() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut repo = Repository::new(conn).unwrap();
+
+        let proj1 = repo.create_project("proj1").unwrap();
+        repo.upsert_pin_for_project("p", &proj1, false).unwrap();
+        // failed calls and calls that change nothing are not journaled
+        assert!(repo.create_project("proj1").is_err());
+        repo.clear_pin("missing").unwrap();
+        repo.undo().unwrap();
+
+        let entries = repo.list_journal(10).unwrap();
+        let entries: Vec<_> = entries
+            .iter()
+            .map(|e| (e.description(), e.is_undone()))
+            .collect();
+        assert_eq!(
+            entries,
+            vec![
+                ("pin 'p' to project 'proj1'", true),
+                ("create project 'proj1'", false),
+            ]
+        );
+        assert_eq!(repo.list_journal(1).unwrap().len(), 1);
     }
 }
@@ -0,0 +1,141 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use anyhow::{Context, Result};
+
+/// A single candidate offered to the picker. The value is what the selection
+/// resolves to, the annotations (pin keys, active markers, ...) are only shown
+/// to the user.
+pub struct Entry {
+    pub value: String,
+    pub annotations: Vec<String>,
+}
+
+impl Entry {
+    pub fn new(value: String) -> Self {
+        Self {
+            value,
+            annotations: Vec::new(),
+        }
+    }
+
+    pub fn annotate(mut self, annotation: impl Into<String>) -> Self {
+        self.annotations.push(annotation.into());
+        self
+    }
+
+    fn line(&self) -> String {
+        std::iter::once(self.value.as_str())
+            .chain(self.annotations.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join("\t")
+    }
+}
+
+/// Run the picker `command`, offering it the `entries` on stdin, and return
+/// the value of the selected entry. `None` is returned when the user cancelled
+/// the picker (dmenu-like programs exit with a non-zero status in that case).
+pub fn pick(command: &[String], entries: &[Entry]) -> Result<Option<String>> {
+    let (program, args) = command
+        .split_first()
+        .ok_or_else(|| anyhow::anyhow!("picker command is empty"))?;
+
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("starting picker '{}'", program))?;
+
+    let input = entries.iter().map(|e| e.line() + "\n").collect::<String>();
+    let mut stdin = child
+        .stdin
+        .take()
+        .expect("INTERNAL ERROR: picker stdin is piped");
+    // write from another thread, a picker that prints before it has read all
+    // of its input would otherwise block on a full stdout pipe while we block
+    // on a full stdin pipe. The picker may also exit without reading all of
+    // its input, which is fine.
+    let writer = std::thread::spawn(move || {
+        let _ = stdin.write_all(input.as_bytes());
+    });
+
+    let output = child
+        .wait_with_output()
+        .with_context(|| format!("waiting for picker '{}'", program))?;
+    writer
+        .join()
+        .map_err(|_| anyhow::anyhow!("writing to picker '{}' panicked", program))?;
+    if !output.status.success() {
+        return Ok(None);
+    }
+
+    let selected = String::from_utf8_lossy(&output.stdout);
+    let selected = selected.lines().next().unwrap_or_default().trim_end();
+    if selected.is_empty() {
+        return Ok(None);
+    }
+
+    // pickers echo back the whole line, but some are configured to only print
+    // the first column, so fall back to matching on the value itself
+    let value = selected.split('\t').next().unwrap_or_default().trim();
+    entries
+        .iter()
+        .find(|e| e.line() == selected || e.value == value)
+        .map(|e| Some(e.value.clone()))
+        .ok_or_else(|| anyhow::anyhow!("picker returned unknown entry '{}'", selected))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Entry, pick};
+
+    fn sh(script: &str) -> Vec<String> {
+        vec!["sh".to_string(), "-c".to_string(), script.to_string()]
+    }
+
+    fn entries() -> Vec<Entry> {
+        vec![
+            Entry::new("proj1".to_string()).annotate("[g]"),
+            Entry::new("proj2".to_string()).annotate("*"),
+        ]
+    }
+
+    #[test]
+    fn test_pick_returns_the_value_of_the_selected_line() {
+        let selected = pick(&sh("sed -n 2p"), &entries()).unwrap();
+        assert_eq!(selected, Some("proj2".to_string()));
+    }
+
+    #[test]
+    fn test_pick_accepts_a_bare_value() {
+        let selected = pick(&sh("cat > /dev/null; echo proj1"), &entries()).unwrap();
+        assert_eq!(selected, Some("proj1".to_string()));
+    }
+
+    #[test]
+    fn test_pick_when_picker_echoes_before_reading_all_input() {
+        // more than fits in a pipe buffer, echoed back while it is read
+        let entries = (0..200_000)
+            .map(|i| Entry::new(format!("proj{}", i)))
+            .collect::<Vec<_>>();
+        let selected = pick(&sh("cat"), &entries).unwrap();
+        assert_eq!(selected, Some("proj0".to_string()));
+    }
+
+    #[test]
+    fn test_pick_when_picker_is_cancelled() {
+        let selected = pick(&sh("exit 1"), &entries()).unwrap();
+        assert!(selected.is_none());
+    }
+
+    #[test]
+    fn test_pick_when_picker_returns_unknown_entry() {
+        assert!(pick(&sh("echo nope"), &entries()).is_err());
+    }
+
+    #[test]
+    fn test_pick_when_command_is_empty() {
+        assert!(pick(&[], &entries()).is_err());
+    }
+}
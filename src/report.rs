@@ -0,0 +1,151 @@
+use std::io::Write;
+
+use anyhow::Result;
+use serde_json::{Map, Value};
+
+use crate::model::FocusTotal;
+
+/// format a number of seconds as H:MM:SS
+fn format_duration(seconds: i64) -> String {
+    format!(
+        "{}:{:02}:{:02}",
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60
+    )
+}
+
+/// quote a CSV field when it contains a separator, quote or line break
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// write one tab separated line per total, with a human readable duration
+pub fn write_table(out: &mut impl Write, totals: &[FocusTotal]) -> Result<()> {
+    for total in totals {
+        match total.view() {
+            Some(view) => writeln!(
+                out,
+                "{}\t{}\t{}",
+                total.project(),
+                view,
+                format_duration(total.seconds())
+            )?,
+            None => writeln!(
+                out,
+                "{}\t{}",
+                total.project(),
+                format_duration(total.seconds())
+            )?,
+        }
+    }
+    Ok(())
+}
+
+/// write the totals as CSV with a header line. The view column is only
+/// present when the totals are grouped by view.
+pub fn write_csv(out: &mut impl Write, totals: &[FocusTotal], by_view: bool) -> Result<()> {
+    if by_view {
+        writeln!(out, "project,view,seconds")?;
+    } else {
+        writeln!(out, "project,seconds")?;
+    }
+    for total in totals {
+        let mut fields = vec![csv_field(total.project())];
+        if by_view {
+            fields.push(csv_field(total.view().unwrap_or_default()));
+        }
+        fields.push(total.seconds().to_string());
+        writeln!(out, "{}", fields.join(","))?;
+    }
+    Ok(())
+}
+
+/// write the totals as a JSON array of objects
+pub fn write_json(out: &mut impl Write, totals: &[FocusTotal]) -> Result<()> {
+    let entries = totals
+        .iter()
+        .map(|total| {
+            let mut entry = Map::new();
+            entry.insert("project".to_string(), Value::from(total.project()));
+            if let Some(view) = total.view() {
+                entry.insert("view".to_string(), Value::from(view));
+            }
+            entry.insert("seconds".to_string(), Value::from(total.seconds()));
+            Value::Object(entry)
+        })
+        .collect::<Vec<_>>();
+    serde_json::to_writer_pretty(&mut *out, &entries)?;
+    writeln!(out)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{csv_field, format_duration, write_csv, write_json, write_table};
+    use crate::model::FocusTotal;
+
+    fn totals(by_view: bool) -> Vec<FocusTotal> {
+        vec![FocusTotal::new(
+            "proj, \"one\"",
+            by_view.then_some("view0"),
+            3723,
+        )]
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(0), "0:00:00");
+        assert_eq!(format_duration(3723), "1:02:03");
+        assert_eq!(format_duration(36000), "10:00:00");
+    }
+
+    #[test]
+    fn test_csv_field_when_field_needs_quoting() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+
+    #[test]
+    fn test_write_table() {
+        let mut out = Vec::new();
+        write_table(&mut out, &totals(true)).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "proj, \"one\"\tview0\t1:02:03\n"
+        );
+    }
+
+    #[test]
+    fn test_write_csv() {
+        let mut out = Vec::new();
+        write_csv(&mut out, &totals(false), false).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "project,seconds\n\"proj, \"\"one\"\"\",3723\n"
+        );
+
+        let mut out = Vec::new();
+        write_csv(&mut out, &totals(true), true).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "project,view,seconds\n\"proj, \"\"one\"\"\",view0,3723\n"
+        );
+    }
+
+    #[test]
+    fn test_write_json() {
+        let mut out = Vec::new();
+        write_json(&mut out, &totals(true)).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!([{"project": "proj, \"one\"", "view": "view0", "seconds": 3723}])
+        );
+    }
+}
@@ -0,0 +1,483 @@
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::model::{PinType, is_window_manager_display_name};
+
+/// The version of the export document written by this build. Documents with
+/// another version are refused on import.
+pub const STATE_VERSION: u32 = 1;
+
+/// The serialization of an export document.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Format {
+    Json,
+    Toml,
+}
+
+impl Format {
+    /// guess the format from a file extension, `.toml` or `.json`
+    pub fn for_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "json" => Some(Format::Json),
+            "toml" => Some(Format::Toml),
+            _ => None,
+        }
+    }
+}
+
+/// The whole muxwm state: projects with their views, pins, pin stacks and
+/// external pins. Focus history is not part of it.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct State {
+    pub version: u32,
+    #[serde(default)]
+    pub projects: Vec<ProjectState>,
+    #[serde(default)]
+    pub pins: Vec<PinState>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stacks: Vec<StackState>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub external_pins: Vec<ExternalPinState>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProjectState {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub root: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tmux_session: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    pub views: Vec<ViewState>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct ViewState {
+    pub name: String,
+    pub position: i64,
+    /// whether this is the project's active view. The first view is active
+    /// when no view is marked.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub active: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub commands: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout: Option<String>,
+}
+
+/// A pin, referring to its project and view by name.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct PinState {
+    pub key: String,
+    #[serde(rename = "type")]
+    pub pin_type: PinType,
+    pub project: String,
+    /// the pinned view, for view and local pins
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub view: Option<String>,
+}
+
+/// A stack pin and its views, in order.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct StackState {
+    pub key: String,
+    pub views: Vec<StackViewState>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct StackViewState {
+    pub project: String,
+    pub view: String,
+}
+
+/// A pin of a workspace, window or mark that muxwm does not manage.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct ExternalPinState {
+    pub key: String,
+    #[serde(rename = "type")]
+    pub pin_type: PinType,
+    pub target: String,
+}
+
+impl ProjectState {
+    /// the view that becomes the project's active view
+    pub fn active_view(&self) -> Option<&ViewState> {
+        self.views
+            .iter()
+            .find(|v| v.active)
+            .or_else(|| self.views.iter().min_by_key(|v| v.position))
+    }
+}
+
+impl State {
+    pub fn parse(contents: &str, format: Format) -> Result<Self> {
+        Ok(match format {
+            Format::Json => serde_json::from_str(contents)?,
+            Format::Toml => toml::from_str(contents)?,
+        })
+    }
+
+    pub fn to_string(&self, format: Format) -> Result<String> {
+        Ok(match format {
+            Format::Json => serde_json::to_string_pretty(self)? + "\n",
+            Format::Toml => toml::to_string(self)?,
+        })
+    }
+
+    /// Check the document for problems that make it impossible to import,
+    /// such as duplicate names or pin keys, and describe each of them.
+    pub fn conflicts(&self) -> Vec<String> {
+        let mut conflicts = Vec::new();
+        if self.version != STATE_VERSION {
+            conflicts.push(format!(
+                "unsupported document version {} (expected {})",
+                self.version, STATE_VERSION
+            ));
+        }
+
+        let mut project_names = HashSet::new();
+        for project in &self.projects {
+            if !project_names.insert(project.name.as_str()) {
+                conflicts.push(format!("duplicate project '{}'", project.name));
+            }
+            if project.views.is_empty() {
+                conflicts.push(format!("project '{}' has no views", project.name));
+            }
+            let mut view_names = HashSet::new();
+            let mut positions = HashSet::new();
+            for view in &project.views {
+                if !view_names.insert(view.name.as_str()) {
+                    conflicts.push(format!(
+                        "duplicate view '{}' in project '{}'",
+                        view.name, project.name
+                    ));
+                }
+                if !positions.insert(view.position) {
+                    conflicts.push(format!(
+                        "duplicate view position {} in project '{}'",
+                        view.position, project.name
+                    ));
+                }
+            }
+            if project.views.iter().filter(|v| v.active).count() > 1 {
+                conflicts.push(format!(
+                    "project '{}' has more than one active view",
+                    project.name
+                ));
+            }
+        }
+
+        let mut global_keys = HashSet::new();
+        let mut local_keys = HashSet::new();
+        let mut pinned = HashSet::new();
+        for pin in &self.pins {
+            let Some(project) = self.projects.iter().find(|p| p.name == pin.project) else {
+                conflicts.push(format!(
+                    "pin '{}' refers to unknown project '{}'",
+                    pin.key, pin.project
+                ));
+                continue;
+            };
+            match (pin.pin_type, &pin.view) {
+                (PinType::Stack, _) => {
+                    conflicts.push(format!(
+                        "pin '{}' is a stack, which belongs in the stacks",
+                        pin.key
+                    ));
+                }
+                (PinType::Workspace | PinType::Window | PinType::Mark, _) => {
+                    conflicts.push(format!(
+                        "pin '{}' is external, which belongs in the external pins",
+                        pin.key
+                    ));
+                }
+                (PinType::Project, None) => {}
+                (PinType::Project, Some(_)) => {
+                    conflicts.push(format!("project pin '{}' names a view", pin.key));
+                }
+                (PinType::View | PinType::Local, None) => {
+                    conflicts.push(format!("{} pin '{}' names no view", pin.pin_type, pin.key));
+                }
+                (PinType::View | PinType::Local, Some(view)) => {
+                    if !project.views.iter().any(|v| &v.name == view) {
+                        conflicts.push(format!(
+                            "pin '{}' refers to unknown view '{}/{}'",
+                            pin.key, pin.project, view
+                        ));
+                    }
+                }
+            }
+
+            let unique_key = match pin.pin_type {
+                PinType::Local => local_keys.insert((pin.project.as_str(), pin.key.as_str())),
+                _ => global_keys.insert(pin.key.as_str()),
+            };
+            if !unique_key {
+                conflicts.push(format!("duplicate pin key '{}'", pin.key));
+            }
+            if !pinned.insert((pin.pin_type, pin.project.as_str(), pin.view.as_deref())) {
+                conflicts.push(format!(
+                    "{} '{}' is pinned more than once",
+                    match pin.view {
+                        Some(_) => "view",
+                        None => "project",
+                    },
+                    match &pin.view {
+                        Some(view) => format!("{}/{}", pin.project, view),
+                        None => pin.project.clone(),
+                    }
+                ));
+            }
+        }
+
+        for stack in &self.stacks {
+            if !global_keys.insert(stack.key.as_str()) {
+                conflicts.push(format!("duplicate pin key '{}'", stack.key));
+            }
+            if stack.views.is_empty() {
+                conflicts.push(format!("pin stack '{}' has no views", stack.key));
+            }
+            let mut members = HashSet::new();
+            for member in &stack.views {
+                let known = self
+                    .projects
+                    .iter()
+                    .find(|p| p.name == member.project)
+                    .is_some_and(|p| p.views.iter().any(|v| v.name == member.view));
+                if !known {
+                    conflicts.push(format!(
+                        "pin stack '{}' refers to unknown view '{}/{}'",
+                        stack.key, member.project, member.view
+                    ));
+                }
+                if !members.insert((member.project.as_str(), member.view.as_str())) {
+                    conflicts.push(format!(
+                        "view '{}/{}' is in pin stack '{}' more than once",
+                        member.project, member.view, stack.key
+                    ));
+                }
+            }
+        }
+
+        for pin in &self.external_pins {
+            if !global_keys.insert(pin.key.as_str()) {
+                conflicts.push(format!("duplicate pin key '{}'", pin.key));
+            }
+            if !pin.pin_type.is_external() {
+                conflicts.push(format!(
+                    "{} pin '{}' is not external",
+                    pin.pin_type, pin.key
+                ));
+            } else if pin.pin_type == PinType::Window && pin.target.parse::<i64>().is_err() {
+                conflicts.push(format!(
+                    "window pin '{}' has the invalid con_id '{}'",
+                    pin.key, pin.target
+                ));
+            } else if pin.pin_type == PinType::Workspace
+                && is_window_manager_display_name(&pin.target)
+            {
+                conflicts.push(format!(
+                    "workspace pin '{}' points to the managed workspace '{}'",
+                    pin.key, pin.target
+                ));
+            }
+        }
+
+        conflicts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        ExternalPinState, Format, PinState, ProjectState, STATE_VERSION, StackState,
+        StackViewState, State, ViewState,
+    };
+    use crate::model::PinType;
+    use std::collections::BTreeMap;
+    use std::path::Path;
+
+    fn view(name: &str, position: i64) -> ViewState {
+        ViewState {
+            name: name.to_string(),
+            position,
+            active: false,
+            commands: Vec::new(),
+            layout: None,
+        }
+    }
+
+    fn state() -> State {
+        State {
+            version: STATE_VERSION,
+            projects: vec![ProjectState {
+                name: "proj1".to_string(),
+                root: Some("/src/proj1".to_string()),
+                tmux_session: None,
+                env: BTreeMap::from([("A".to_string(), "1".to_string())]),
+                views: vec![
+                    view("code", 1),
+                    ViewState {
+                        active: true,
+                        commands: vec!["alacritty".to_string()],
+                        ..view("term", 2)
+                    },
+                ],
+            }],
+            pins: vec![
+                PinState {
+                    key: "p".to_string(),
+                    pin_type: PinType::Project,
+                    project: "proj1".to_string(),
+                    view: None,
+                },
+                PinState {
+                    key: "1".to_string(),
+                    pin_type: PinType::Local,
+                    project: "proj1".to_string(),
+                    view: Some("code".to_string()),
+                },
+            ],
+            stacks: vec![StackState {
+                key: "t".to_string(),
+                views: vec![
+                    StackViewState {
+                        project: "proj1".to_string(),
+                        view: "term".to_string(),
+                    },
+                    StackViewState {
+                        project: "proj1".to_string(),
+                        view: "code".to_string(),
+                    },
+                ],
+            }],
+            external_pins: vec![ExternalPinState {
+                key: "m".to_string(),
+                pin_type: PinType::Workspace,
+                target: "music".to_string(),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_format_for_path() {
+        assert_eq!(Format::for_path(Path::new("a.json")), Some(Format::Json));
+        assert_eq!(Format::for_path(Path::new("a/b.toml")), Some(Format::Toml));
+        assert_eq!(Format::for_path(Path::new("a.txt")), None);
+        assert_eq!(Format::for_path(Path::new("a")), None);
+    }
+
+    #[test]
+    fn test_round_trip_through_json_and_toml() {
+        for format in [Format::Json, Format::Toml] {
+            let text = state().to_string(format).unwrap();
+            assert_eq!(State::parse(&text, format).unwrap(), state());
+        }
+    }
+
+    #[test]
+    fn test_active_view_defaults_to_the_first_position() {
+        let mut state = state();
+        assert_eq!(state.projects[0].active_view().unwrap().name, "term");
+
+        state.projects[0].views[1].active = false;
+        assert_eq!(state.projects[0].active_view().unwrap().name, "code");
+    }
+
+    #[test]
+    fn test_conflicts_when_document_is_valid() {
+        assert!(state().conflicts().is_empty());
+    }
+
+    #[test]
+    fn test_conflicts_when_names_and_keys_are_duplicated() {
+        let mut state = state();
+        state.version = STATE_VERSION + 1;
+        state.projects.push(ProjectState {
+            name: "proj1".to_string(),
+            root: None,
+            tmux_session: None,
+            env: BTreeMap::new(),
+            views: vec![view("a", 1), view("a", 1)],
+        });
+        state.pins.push(PinState {
+            key: "p".to_string(),
+            pin_type: PinType::View,
+            project: "proj1".to_string(),
+            view: Some("missing".to_string()),
+        });
+        state.pins.push(PinState {
+            key: "q".to_string(),
+            pin_type: PinType::Project,
+            project: "proj2".to_string(),
+            view: None,
+        });
+
+        assert_eq!(
+            state.conflicts(),
+            vec![
+                format!(
+                    "unsupported document version {} (expected {})",
+                    STATE_VERSION + 1,
+                    STATE_VERSION
+                ),
+                "duplicate project 'proj1'".to_string(),
+                "duplicate view 'a' in project 'proj1'".to_string(),
+                "duplicate view position 1 in project 'proj1'".to_string(),
+                "pin 'p' refers to unknown view 'proj1/missing'".to_string(),
+                "duplicate pin key 'p'".to_string(),
+                "pin 'q' refers to unknown project 'proj2'".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_conflicts_when_stacks_are_invalid() {
+        let mut state = state();
+        state.stacks[0].key = "p".to_string();
+        state.stacks[0].views[1].view = "term".to_string();
+        state.stacks.push(StackState {
+            key: "s".to_string(),
+            views: vec![StackViewState {
+                project: "proj2".to_string(),
+                view: "code".to_string(),
+            }],
+        });
+        state.stacks.push(StackState {
+            key: "e".to_string(),
+            views: Vec::new(),
+        });
+        state.external_pins.push(ExternalPinState {
+            key: "w".to_string(),
+            pin_type: PinType::Window,
+            target: "firefox".to_string(),
+        });
+        state.external_pins.push(ExternalPinState {
+            key: "x".to_string(),
+            pin_type: PinType::View,
+            target: "music".to_string(),
+        });
+        state.external_pins.push(ExternalPinState {
+            key: "y".to_string(),
+            pin_type: PinType::Workspace,
+            target: "proj1#code".to_string(),
+        });
+
+        assert_eq!(
+            state.conflicts(),
+            vec![
+                "duplicate pin key 'p'".to_string(),
+                "view 'proj1/term' is in pin stack 'p' more than once".to_string(),
+                "pin stack 's' refers to unknown view 'proj2/code'".to_string(),
+                "pin stack 'e' has no views".to_string(),
+                "window pin 'w' has the invalid con_id 'firefox'".to_string(),
+                "view pin 'x' is not external".to_string(),
+                "workspace pin 'y' points to the managed workspace 'proj1#code'".to_string(),
+            ]
+        );
+    }
+}
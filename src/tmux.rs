@@ -0,0 +1,145 @@
+use std::process::{Command, Output};
+
+use anyhow::{Context, Result};
+
+/// A handle on a tmux server, the default one or the one listening on a named
+/// socket (`tmux -L`).
+pub struct Tmux {
+    socket_name: Option<String>,
+}
+
+impl Tmux {
+    pub fn new(socket_name: Option<String>) -> Self {
+        Self { socket_name }
+    }
+
+    pub fn has_session(&self, session: &str) -> Result<bool> {
+        // `=` makes tmux match the session name exactly instead of by prefix
+        Ok(self
+            .run(&["has-session", "-t", &format!("={}", session)])?
+            .status
+            .success())
+    }
+
+    /// Create a detached session, starting in `dir` when given.
+    pub fn new_session(&self, session: &str, dir: Option<&str>) -> Result<()> {
+        let mut args = vec!["new-session", "-d", "-s", session];
+        if let Some(dir) = dir {
+            args.extend(["-c", dir]);
+        }
+        self.run_checked(&args)
+    }
+
+    /// Create the session unless it already exists.
+    pub fn ensure_session(&self, session: &str, dir: Option<&str>) -> Result<()> {
+        if !self.has_session(session)? {
+            self.new_session(session, dir)?;
+        }
+        Ok(())
+    }
+
+    pub fn kill_session(&self, session: &str) -> Result<()> {
+        self.run_checked(&["kill-session", "-t", &format!("={}", session)])
+    }
+
+    fn run(&self, args: &[&str]) -> Result<Output> {
+        let mut cmd = Command::new("tmux");
+        if let Some(socket_name) = &self.socket_name {
+            cmd.args(["-L", socket_name]);
+        }
+        cmd.args(args)
+            .output()
+            .with_context(|| format!("running tmux {}", args.join(" ")))
+    }
+
+    fn run_checked(&self, args: &[&str]) -> Result<()> {
+        let output = self.run(args)?;
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "tmux {} failed: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// The tmux session name for a project name: tmux does not allow `:` and `.`
+/// in session names.
+pub fn session_name_for(project_name: &str) -> String {
+    project_name.replace([':', '.'], "-")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Tmux, session_name_for};
+    use std::process::Command;
+
+    /// a tmux server private to the test, killed when dropped
+    struct TestServer(Tmux);
+
+    impl TestServer {
+        fn start(name: &str) -> Option<Self> {
+            // skip the test when tmux is not installed
+            Command::new("tmux").arg("-V").output().ok()?;
+            let socket_name = format!("muxwm-test-{}-{}", name, std::process::id());
+            Some(Self(Tmux::new(Some(socket_name))))
+        }
+    }
+
+    impl Drop for TestServer {
+        fn drop(&mut self) {
+            let _ = self.0.run(&["kill-server"]);
+        }
+    }
+
+    #[test]
+    fn test_ensure_session_creates_missing_session_once() {
+        let Some(server) = TestServer::start("ensure") else {
+            return;
+        };
+        let tmux = &server.0;
+
+        assert!(!tmux.has_session("proj1").unwrap());
+        tmux.ensure_session("proj1", Some("/")).unwrap();
+        assert!(tmux.has_session("proj1").unwrap());
+
+        // a second call finds the existing session
+        tmux.ensure_session("proj1", Some("/")).unwrap();
+        assert!(tmux.has_session("proj1").unwrap());
+    }
+
+    #[test]
+    fn test_has_session_does_not_match_prefixes() {
+        let Some(server) = TestServer::start("prefix") else {
+            return;
+        };
+        let tmux = &server.0;
+
+        tmux.new_session("proj10", None).unwrap();
+        assert!(!tmux.has_session("proj1").unwrap());
+    }
+
+    #[test]
+    fn test_kill_session() {
+        let Some(server) = TestServer::start("kill") else {
+            return;
+        };
+        let tmux = &server.0;
+
+        tmux.new_session("proj1", None).unwrap();
+        tmux.new_session("proj2", None).unwrap();
+        tmux.kill_session("proj1").unwrap();
+        assert!(!tmux.has_session("proj1").unwrap());
+        assert!(tmux.has_session("proj2").unwrap());
+
+        assert!(tmux.kill_session("proj1").is_err());
+    }
+
+    #[test]
+    fn test_session_name_for() {
+        assert_eq!(session_name_for("muxwm"), "muxwm");
+        assert_eq!(session_name_for("example.com:8080"), "example-com-8080");
+    }
+}
@@ -0,0 +1,708 @@
+use anyhow::{Context, Result};
+use i3ipc::I3Connection;
+use i3ipc::reply::{Node, NodeBorder, NodeLayout, NodeType, WindowProperty};
+use serde_json::{Map, Value, json};
+
+pub struct WindowManager {
+    connection: I3Connection,
+}
+
+/// Where a window is sent to.
+#[derive(Debug, PartialEq)]
+pub enum Destination {
+    /// a workspace, created if needed
+    Workspace(String),
+    /// the workspace of the window with the con_id
+    Window(i64),
+    /// next to the window with the mark
+    Mark(String),
+}
+
+impl std::fmt::Display for Destination {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Destination::Workspace(name) => write!(f, "workspace '{}'", name),
+            Destination::Window(con_id) => write!(f, "the workspace of window {}", con_id),
+            Destination::Mark(mark) => write!(f, "mark '{}'", mark),
+        }
+    }
+}
+
+/// A window, with the workspace it is on.
+#[derive(Debug, PartialEq)]
+pub struct Window {
+    con_id: i64,
+    workspace: String,
+    class: Option<String>,
+    title: Option<String>,
+}
+
+impl Window {
+    pub fn con_id(&self) -> i64 {
+        self.con_id
+    }
+
+    pub fn workspace(&self) -> &str {
+        &self.workspace
+    }
+
+    pub fn class(&self) -> Option<&str> {
+        self.class.as_deref()
+    }
+
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+}
+
+/// The windows to close, with the i3 commands that close them.
+#[derive(Debug, PartialEq)]
+pub struct ClosePlan {
+    windows: Vec<Window>,
+    commands: Vec<String>,
+}
+
+impl ClosePlan {
+    pub fn windows(&self) -> &[Window] {
+        &self.windows
+    }
+}
+
+impl WindowManager {
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            connection: I3Connection::connect().context("connecting to i3")?,
+        })
+    }
+
+    pub fn focus(&mut self, workspace: &str) -> Result<()> {
+        let cmd = format!("workspace {}", workspace);
+        self.connection
+            .run_command(&cmd)
+            .with_context(|| format!("running `workspace` command with {}", workspace))?;
+        Ok(())
+    }
+
+    pub fn get_active_workspace_name(&mut self) -> Result<String> {
+        let result = self.connection.get_workspaces()?;
+        result
+            .workspaces
+            .iter()
+            .find(|w| w.focused)
+            .map(|w| w.name.clone())
+            .ok_or(anyhow::anyhow!("no active workspace"))
+    }
+
+    pub fn get_workspace_names(&mut self) -> Result<Vec<String>> {
+        let result = self
+            .connection
+            .get_workspaces()
+            .context("getting workspaces")?;
+        Ok(result.workspaces.iter().map(|w| w.name.clone()).collect())
+    }
+
+    pub fn rename_workspace(&mut self, old_name: &str, new_name: &str) -> Result<()> {
+        let cmd = format!("rename workspace \"{}\" to \"{}\"", old_name, new_name);
+        self.connection
+            .run_command(&cmd)
+            .context("renameing workspace")?;
+        Ok(())
+    }
+
+    /// run a shell command line through i3's `exec`, so that its windows are
+    /// placed on the focused workspace
+    pub fn exec(&mut self, command: &str) -> Result<()> {
+        let cmd = format!("exec --no-startup-id {}", quote(command));
+        self.connection
+            .run_command(&cmd)
+            .with_context(|| format!("running `exec` command with {}", command))?;
+        Ok(())
+    }
+
+    /// capture the layout of the workspace in the format understood by i3's
+    /// `append_layout`, with every window replaced by a placeholder that
+    /// swallows windows of the same class and instance
+    pub fn get_workspace_layout(&mut self, workspace: &str) -> Result<String> {
+        let tree = self.connection.get_tree().context("getting tree")?;
+        let ws = find_workspace(&tree, workspace)
+            .ok_or_else(|| anyhow::anyhow!("no workspace named '{}'", workspace))?;
+        let layout = workspace_layout(ws);
+        if layout.is_empty() {
+            return Err(anyhow::anyhow!("workspace '{}' has no windows", workspace));
+        }
+
+        // i3 expects the top level containers as consecutive JSON documents
+        layout
+            .iter()
+            .map(|con| serde_json::to_string_pretty(con).map_err(Into::into))
+            .collect::<Result<Vec<_>>>()
+            .map(|docs| docs.join("\n"))
+    }
+
+    /// append a layout captured by `get_workspace_layout` to the focused
+    /// workspace
+    pub fn append_layout(&mut self, layout: &str) -> Result<()> {
+        let path = std::env::temp_dir().join(format!("muxwm-layout-{}.json", std::process::id()));
+        std::fs::write(&path, layout)
+            .with_context(|| format!("writing layout to {}", path.display()))?;
+
+        let cmd = format!("append_layout {}", quote(&path.to_string_lossy()));
+        let result = self.connection.run_command(&cmd);
+        let _ = std::fs::remove_file(&path);
+
+        let outcome = result.context("running `append_layout` command")?;
+        if let Some(error) = outcome.outcomes.iter().find_map(|o| o.error.clone()) {
+            return Err(anyhow::anyhow!("appending layout: {}", error));
+        }
+        Ok(())
+    }
+
+    /// whether the workspace exists and holds at least one window
+    pub fn workspace_has_windows(&mut self, workspace: &str) -> Result<bool> {
+        let tree = self.connection.get_tree().context("getting tree")?;
+        Ok(find_workspace(&tree, workspace).is_some_and(has_windows))
+    }
+
+    /// list the windows on all workspaces, including the scratchpad
+    pub fn list_windows(&mut self) -> Result<Vec<Window>> {
+        let tree = self.connection.get_tree().context("getting tree")?;
+        let mut windows = Vec::new();
+        collect_windows(&tree, None, &mut windows);
+        Ok(windows)
+    }
+
+    /// the con_id of the focused window
+    pub fn get_focused_window_id(&mut self) -> Result<i64> {
+        let tree = self.connection.get_tree().context("getting tree")?;
+        focused_window_id(&tree).ok_or_else(|| anyhow::anyhow!("no focused window"))
+    }
+
+    /// focus the window with the con_id, switching to its workspace
+    pub fn focus_window(&mut self, con_id: i64) -> Result<()> {
+        let tree = self.connection.get_tree().context("getting tree")?;
+        if find_window(&tree, &|n| n.id == con_id).is_none() {
+            return Err(anyhow::anyhow!("no window with con_id {}", con_id));
+        }
+        self.connection
+            .run_command(&format!("[con_id={}] focus", con_id))
+            .with_context(|| format!("focusing window {}", con_id))?;
+        Ok(())
+    }
+
+    /// move the window to the destination and, with `follow`, focus it there
+    pub fn send_window(
+        &mut self,
+        con_id: i64,
+        destination: &Destination,
+        follow: bool,
+    ) -> Result<()> {
+        let tree = self.connection.get_tree().context("getting tree")?;
+        for cmd in send_window_commands(&tree, con_id, destination, follow)? {
+            self.run_checked(&cmd)
+                .with_context(|| format!("moving window {} to {}", con_id, destination))?;
+        }
+        Ok(())
+    }
+
+    /// plan closing every window on the workspaces, see `close_windows`
+    pub fn plan_close_windows(&mut self, workspaces: &[String]) -> Result<ClosePlan> {
+        let tree = self.connection.get_tree().context("getting tree")?;
+        Ok(close_windows_plan(&tree, workspaces))
+    }
+
+    /// close the windows of the plan, the way i3's `kill` does
+    pub fn close_windows(&mut self, plan: &ClosePlan) -> Result<()> {
+        for (window, cmd) in plan.windows.iter().zip(&plan.commands) {
+            self.run_checked(cmd)
+                .with_context(|| format!("killing window {}", window.con_id))?;
+        }
+        Ok(())
+    }
+
+    /// run a command, failing with i3's error when it reports one
+    fn run_checked(&mut self, cmd: &str) -> Result<()> {
+        let outcome = self
+            .connection
+            .run_command(cmd)
+            .with_context(|| format!("running `{}`", cmd))?;
+        if let Some(error) = outcome.outcomes.iter().find_map(|o| o.error.clone()) {
+            return Err(anyhow::anyhow!(error));
+        }
+        Ok(())
+    }
+
+    /// focus the window with the mark, switching to its workspace
+    pub fn focus_mark(&mut self, mark: &str) -> Result<()> {
+        let marks = self.connection.get_marks().context("getting marks")?;
+        if !marks.marks.iter().any(|m| m == mark) {
+            return Err(anyhow::anyhow!("no window marked '{}'", mark));
+        }
+        let criteria = quote(&format!("^{}$", escape_regex(mark)));
+        self.connection
+            .run_command(&format!("[con_mark={}] focus", criteria))
+            .with_context(|| format!("focusing window marked '{}'", mark))?;
+        Ok(())
+    }
+}
+
+/// quote a string as an argument to an i3 command
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn find_workspace<'a>(node: &'a Node, name: &str) -> Option<&'a Node> {
+    if node.nodetype == NodeType::Workspace && node.name.as_deref() == Some(name) {
+        return Some(node);
+    }
+    node.nodes
+        .iter()
+        .chain(node.floating_nodes.iter())
+        .find_map(|child| find_workspace(child, name))
+}
+
+/// find a window, that is a container holding an X11 window, that matches
+fn find_window<'a>(node: &'a Node, matches: &impl Fn(&Node) -> bool) -> Option<&'a Node> {
+    if node.window.is_some() && matches(node) {
+        return Some(node);
+    }
+    node.nodes
+        .iter()
+        .chain(node.floating_nodes.iter())
+        .find_map(|child| find_window(child, matches))
+}
+
+/// the i3 commands that move the window to the destination and, with
+/// `follow`, focus it there
+fn send_window_commands(
+    tree: &Node,
+    con_id: i64,
+    destination: &Destination,
+    follow: bool,
+) -> Result<Vec<String>> {
+    if find_window(tree, &|n| n.id == con_id).is_none() {
+        return Err(anyhow::anyhow!("no window with con_id {}", con_id));
+    }
+    let to = match destination {
+        Destination::Workspace(name) => format!("workspace {}", quote(name)),
+        Destination::Window(target_id) => {
+            let mut windows = Vec::new();
+            collect_windows(tree, None, &mut windows);
+            let window = windows
+                .iter()
+                .find(|w| w.con_id == *target_id)
+                .ok_or_else(|| anyhow::anyhow!("no window with con_id {}", target_id))?;
+            format!("workspace {}", quote(&window.workspace))
+        }
+        Destination::Mark(mark) => format!("mark {}", quote(mark)),
+    };
+
+    let mut commands = vec![format!("[con_id={}] move container to {}", con_id, to)];
+    if follow {
+        commands.push(format!("[con_id={}] focus", con_id));
+    }
+    Ok(commands)
+}
+
+/// plan closing the windows on the workspaces, in tree order, such as the
+/// views of a project. Windows elsewhere, on other workspaces or on the
+/// scratchpad, are left alone.
+fn close_windows_plan(tree: &Node, workspaces: &[String]) -> ClosePlan {
+    let mut windows = Vec::new();
+    collect_windows(tree, None, &mut windows);
+    windows.retain(|w| workspaces.contains(&w.workspace));
+    let commands = windows
+        .iter()
+        .map(|w| format!("[con_id={}] kill", w.con_id))
+        .collect();
+    ClosePlan { windows, commands }
+}
+
+/// the con_id of the focused window, `None` when the focused container is not
+/// a window, such as an empty workspace
+fn focused_window_id(tree: &Node) -> Option<i64> {
+    find_window(tree, &|n| n.focused).map(|n| n.id)
+}
+
+/// collect the windows below the node, with the workspace they are on.
+/// Windows outside of any workspace are left out.
+fn collect_windows(node: &Node, workspace: Option<&str>, windows: &mut Vec<Window>) {
+    let workspace = match node.nodetype {
+        NodeType::Workspace => node.name.as_deref(),
+        _ => workspace,
+    };
+    if let (Some(_), Some(workspace)) = (node.window, workspace) {
+        windows.push(Window {
+            con_id: node.id,
+            workspace: workspace.to_string(),
+            class: node
+                .window_properties
+                .as_ref()
+                .and_then(|props| props.get(&WindowProperty::Class))
+                .cloned(),
+            title: node.name.clone(),
+        });
+    }
+    for child in node.nodes.iter().chain(node.floating_nodes.iter()) {
+        collect_windows(child, workspace, windows);
+    }
+}
+
+/// the layouts of the workspace's tiling and floating containers, leaving out
+/// containers without any windows as there would be nothing to swallow
+fn workspace_layout(workspace: &Node) -> Vec<Value> {
+    let tiling = workspace.nodes.iter().map(|n| (n, "con"));
+    let floating = workspace.floating_nodes.iter().map(|n| (n, "floating_con"));
+    tiling
+        .chain(floating)
+        .filter(|(n, _)| has_windows(n))
+        .map(|(n, con_type)| container_layout(n, con_type))
+        .collect()
+}
+
+fn container_layout(node: &Node, con_type: &str) -> Value {
+    let mut con = Map::new();
+    con.insert("type".to_string(), json!(con_type));
+    con.insert("percent".to_string(), json!(node.percent));
+    con.insert("border".to_string(), json!(border_name(&node.border)));
+
+    if node.window.is_some() {
+        let mut criteria = Map::new();
+        for (property, key) in [
+            (WindowProperty::Class, "class"),
+            (WindowProperty::Instance, "instance"),
+        ] {
+            if let Some(value) = node
+                .window_properties
+                .as_ref()
+                .and_then(|props| props.get(&property))
+            {
+                criteria.insert(key.to_string(), json!(format!("^{}$", escape_regex(value))));
+            }
+        }
+        con.insert("swallows".to_string(), json!([criteria]));
+    } else {
+        con.insert("layout".to_string(), json!(layout_name(&node.layout)));
+        let children = node
+            .nodes
+            .iter()
+            .filter(|n| has_windows(n))
+            .map(|n| container_layout(n, "con"))
+            .collect::<Vec<_>>();
+        con.insert("nodes".to_string(), json!(children));
+    }
+
+    Value::Object(con)
+}
+
+fn layout_name(layout: &NodeLayout) -> &'static str {
+    match layout {
+        NodeLayout::SplitV => "splitv",
+        NodeLayout::Stacked => "stacked",
+        NodeLayout::Tabbed => "tabbed",
+        _ => "splith",
+    }
+}
+
+fn border_name(border: &NodeBorder) -> &'static str {
+    match border {
+        NodeBorder::None => "none",
+        NodeBorder::Pixel => "pixel",
+        _ => "normal",
+    }
+}
+
+/// escape the characters that have a meaning in the PCRE patterns i3 uses for
+/// swallow criteria
+fn escape_regex(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        if "\\^$.|?*+()[]{}".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn has_windows(node: &Node) -> bool {
+    node.window.is_some()
+        || node
+            .nodes
+            .iter()
+            .chain(node.floating_nodes.iter())
+            .any(has_windows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Destination, close_windows_plan, collect_windows, escape_regex, focused_window_id,
+        send_window_commands, workspace_layout,
+    };
+    use i3ipc::reply::{Node, NodeBorder, NodeLayout, NodeType, WindowProperty};
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    fn con(nodetype: NodeType, layout: NodeLayout, nodes: Vec<Node>) -> Node {
+        Node {
+            focus: Vec::new(),
+            nodes,
+            floating_nodes: Vec::new(),
+            id: 0,
+            name: None,
+            nodetype,
+            border: NodeBorder::Normal,
+            current_border_width: 0,
+            layout,
+            percent: Some(0.5),
+            rect: (0, 0, 0, 0),
+            window_rect: (0, 0, 0, 0),
+            deco_rect: (0, 0, 0, 0),
+            geometry: (0, 0, 0, 0),
+            window: None,
+            window_properties: None,
+            urgent: false,
+            focused: false,
+        }
+    }
+
+    fn window(class: &str, instance: &str) -> Node {
+        let mut node = con(NodeType::Con, NodeLayout::SplitH, Vec::new());
+        node.window = Some(1);
+        node.window_properties = Some(HashMap::from([
+            (WindowProperty::Class, class.to_string()),
+            (WindowProperty::Instance, instance.to_string()),
+        ]));
+        node
+    }
+
+    #[test]
+    fn test_workspace_layout_swallows_windows_by_class_and_instance() {
+        let split = con(
+            NodeType::Con,
+            NodeLayout::Tabbed,
+            vec![
+                window("Alacritty", "alacritty"),
+                window("firefox", "Navigator"),
+            ],
+        );
+        let empty = con(NodeType::Con, NodeLayout::SplitV, Vec::new());
+        let ws = con(NodeType::Workspace, NodeLayout::SplitH, vec![split, empty]);
+
+        let layout = workspace_layout(&ws);
+        assert_eq!(layout.len(), 1);
+        assert_eq!(layout[0]["layout"], json!("tabbed"));
+        assert_eq!(
+            layout[0]["nodes"][0]["swallows"],
+            json!([{"class": "^Alacritty$", "instance": "^alacritty$"}])
+        );
+        assert_eq!(
+            layout[0]["nodes"][1]["swallows"][0]["instance"],
+            json!("^Navigator$")
+        );
+    }
+
+    #[test]
+    fn test_workspace_layout_of_an_empty_workspace() {
+        let ws = con(NodeType::Workspace, NodeLayout::SplitH, Vec::new());
+        assert!(workspace_layout(&ws).is_empty());
+    }
+
+    #[test]
+    fn test_collect_windows_records_their_workspace() {
+        let mut tiled = window("Alacritty", "alacritty");
+        tiled.id = 10;
+        tiled.name = Some("~/src".to_string());
+        let mut floating = window("firefox", "Navigator");
+        floating.id = 11;
+        let mut ws1 = con(NodeType::Workspace, NodeLayout::SplitH, vec![tiled]);
+        ws1.name = Some("proj1#view0".to_string());
+        let mut ws2 = con(NodeType::Workspace, NodeLayout::SplitH, Vec::new());
+        ws2.name = Some("music".to_string());
+        ws2.floating_nodes = vec![floating];
+        let root = con(NodeType::Root, NodeLayout::SplitH, vec![ws1, ws2]);
+
+        let mut windows = Vec::new();
+        collect_windows(&root, None, &mut windows);
+        let windows = windows
+            .iter()
+            .map(|w| (w.con_id(), w.workspace(), w.class(), w.title()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            windows,
+            vec![
+                (10, "proj1#view0", Some("Alacritty"), Some("~/src")),
+                (11, "music", Some("firefox"), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_collect_windows_includes_the_scratchpad() {
+        let mut hidden = window("Keepassxc", "keepassxc");
+        hidden.id = 12;
+        let mut scratch = con(NodeType::Workspace, NodeLayout::SplitH, Vec::new());
+        scratch.name = Some("__i3_scratch".to_string());
+        scratch.floating_nodes = vec![con(NodeType::FloatingCon, NodeLayout::SplitH, vec![hidden])];
+        let root = con(NodeType::Root, NodeLayout::SplitH, vec![scratch]);
+
+        let mut windows = Vec::new();
+        collect_windows(&root, None, &mut windows);
+        let windows = windows
+            .iter()
+            .map(|w| (w.con_id(), w.workspace()))
+            .collect::<Vec<_>>();
+        assert_eq!(windows, vec![(12, "__i3_scratch")]);
+    }
+
+    #[test]
+    fn test_focused_window_id() {
+        let mut focused = window("firefox", "Navigator");
+        focused.id = 11;
+        focused.focused = true;
+        let mut ws = con(NodeType::Workspace, NodeLayout::SplitH, vec![focused]);
+        ws.name = Some("music".to_string());
+        let root = con(NodeType::Root, NodeLayout::SplitH, vec![ws]);
+        assert_eq!(focused_window_id(&root), Some(11));
+
+        // a focused empty workspace holds no window
+        let mut ws = con(NodeType::Workspace, NodeLayout::SplitH, Vec::new());
+        ws.focused = true;
+        let root = con(NodeType::Root, NodeLayout::SplitH, vec![ws]);
+        assert_eq!(focused_window_id(&root), None);
+    }
+
+    fn two_workspaces() -> Node {
+        let mut sent = window("Alacritty", "alacritty");
+        sent.id = 10;
+        let mut other = window("firefox", "Navigator");
+        other.id = 11;
+        let mut ws1 = con(NodeType::Workspace, NodeLayout::SplitH, vec![sent]);
+        ws1.name = Some("proj1#view0".to_string());
+        let mut ws2 = con(NodeType::Workspace, NodeLayout::SplitH, vec![other]);
+        ws2.name = Some("music".to_string());
+        con(NodeType::Root, NodeLayout::SplitH, vec![ws1, ws2])
+    }
+
+    #[test]
+    fn test_send_window_commands_to_a_workspace() {
+        let commands = send_window_commands(
+            &two_workspaces(),
+            10,
+            &Destination::Workspace("proj2#code".to_string()),
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            commands,
+            vec!["[con_id=10] move container to workspace \"proj2#code\""]
+        );
+    }
+
+    #[test]
+    fn test_send_window_commands_to_the_workspace_of_a_window() {
+        let tree = two_workspaces();
+        let commands = send_window_commands(&tree, 10, &Destination::Window(11), false).unwrap();
+        assert_eq!(
+            commands,
+            vec!["[con_id=10] move container to workspace \"music\""]
+        );
+
+        assert!(send_window_commands(&tree, 10, &Destination::Window(99), false).is_err());
+    }
+
+    #[test]
+    fn test_send_window_commands_to_a_mark() {
+        let commands = send_window_commands(
+            &two_workspaces(),
+            10,
+            &Destination::Mark("browser".to_string()),
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            commands,
+            vec!["[con_id=10] move container to mark \"browser\""]
+        );
+    }
+
+    #[test]
+    fn test_send_window_commands_with_follow() {
+        let commands = send_window_commands(
+            &two_workspaces(),
+            10,
+            &Destination::Workspace("music".to_string()),
+            true,
+        )
+        .unwrap();
+        assert_eq!(
+            commands,
+            vec![
+                "[con_id=10] move container to workspace \"music\"",
+                "[con_id=10] focus",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_send_window_commands_when_window_is_unknown() {
+        let destination = Destination::Workspace("music".to_string());
+        assert!(send_window_commands(&two_workspaces(), 99, &destination, false).is_err());
+    }
+
+    /// a tree with windows on two views of proj1, a view of proj2, an
+    /// unmanaged workspace and the scratchpad
+    fn tree_of_projects() -> Node {
+        let workspaces = [
+            ("proj1#view0", 10),
+            ("proj1#view1", 11),
+            ("proj2#view0", 12),
+            ("music", 13),
+            ("__i3_scratch", 14),
+        ];
+        let nodes = workspaces
+            .into_iter()
+            .map(|(name, con_id)| {
+                let mut window = window("Alacritty", "alacritty");
+                window.id = con_id;
+                let mut ws = con(NodeType::Workspace, NodeLayout::SplitH, vec![window]);
+                ws.name = Some(name.to_string());
+                ws
+            })
+            .collect();
+        con(NodeType::Root, NodeLayout::SplitH, nodes)
+    }
+
+    fn proj1_workspaces() -> Vec<String> {
+        vec!["proj1#view0".to_string(), "proj1#view1".to_string()]
+    }
+
+    #[test]
+    fn test_close_windows_plan_keeps_windows_of_other_workspaces() {
+        let plan = close_windows_plan(&tree_of_projects(), &proj1_workspaces());
+        let windows = plan
+            .windows()
+            .iter()
+            .map(|w| (w.con_id(), w.workspace()))
+            .collect::<Vec<_>>();
+        assert_eq!(windows, vec![(10, "proj1#view0"), (11, "proj1#view1")]);
+        assert_eq!(plan.commands, vec!["[con_id=10] kill", "[con_id=11] kill"]);
+    }
+
+    #[test]
+    fn test_close_windows_plan_when_there_are_no_windows() {
+        let workspaces = vec!["proj3#view0".to_string()];
+        let plan = close_windows_plan(&tree_of_projects(), &workspaces);
+        assert!(plan.windows().is_empty());
+        assert!(plan.commands.is_empty());
+
+        let plan = close_windows_plan(&tree_of_projects(), &[]);
+        assert!(plan.windows().is_empty());
+    }
+
+    #[test]
+    fn test_escape_regex() {
+        assert_eq!(escape_regex("Org.gnome.Nautilus"), "Org\\.gnome\\.Nautilus");
+        assert_eq!(escape_regex("a(b)"), "a\\(b\\)");
+    }
+}